@@ -1,7 +1,9 @@
 use crate::PositionalInvertedIndex;
+use crate::idx::{StemmingAnalyzer, TokenOrdering};
 use crate::query_tokens::{
     generate_queries_from_fixed_dictionary,
     generate_queries_from_distribution,
+    generate_boolean_queries_from_dictionary,
     pull_query_from_paragraph,
     QueryTokenDistribution
 };
@@ -16,32 +18,64 @@ use std::{
     time::Instant,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn benchmark_index(
-    filenames: Vec<String>, 
-    query_frequency: usize, 
-    num_queries: usize, 
+    filenames: Vec<String>,
+    query_frequency: usize,
+    num_queries: usize,
     max_query_tokens: usize,
     query_token_distribution: QueryTokenDistribution,
+    token_search_ordering: TokenOrdering,
     target_directory: &str,
+    fuzzy_max_edits: Option<usize>,
+    use_stemming: bool,
 ) -> Result<(), Box<dyn Error>> {
-    let mut index = PositionalInvertedIndex::new();
+    let mut index = if use_stemming {
+        PositionalInvertedIndex::with_analyzer(Box::new(StemmingAnalyzer::new(true)))
+    } else {
+        PositionalInvertedIndex::new()
+    };
+    index.set_ordering(token_search_ordering);
 
     fs::create_dir_all(target_directory)?;
 
-    let indexing_csv_path = Path::new(target_directory).join("indexing_data.csv");
-    let querying_csv_path = Path::new(target_directory).join("querying_data.csv");
-    let size_csv_path = Path::new(target_directory).join("size_data.csv");
+    // Named to match what `plot::plot_index_latency`/`plot_query_latency`/
+    // `plot_posting_list_distribution`/`plot_term_list_sizes` (and, in turn,
+    // `plot::generate_report`) read back out of `target_directory`.
+    let indexing_csv_path = Path::new(target_directory).join("index_latency.csv");
+    let querying_csv_path = Path::new(target_directory).join("query_latency.csv");
+    let size_csv_path = Path::new(target_directory).join("posting_list_sizes.csv");
+    let term_list_size_csv_path = Path::new(target_directory).join("term_list_sizes.csv");
     let final_sizes_csv_path = Path::new(target_directory).join("final_sizes.csv");
+    let intersection_csv_path = Path::new(target_directory).join("intersection_latency.csv");
+    let ranking_csv_path = Path::new(target_directory).join("ranking_data.csv");
+    let evaluation_csv_path = Path::new(target_directory).join("evaluation.csv");
+    let evaluation_summary_csv_path = Path::new(target_directory).join("evaluation_summary.csv");
 
     let mut indexing_writer = Writer::from_path(indexing_csv_path)?;
     let mut querying_writer = Writer::from_path(querying_csv_path)?;
     let mut size_writer = Writer::from_path(size_csv_path)?;
+    let mut term_list_size_writer = Writer::from_path(term_list_size_csv_path)?;
     let mut final_sizes_writer = Writer::from_path(final_sizes_csv_path)?;
-
-    indexing_writer.write_record(&["Document Count", "Indexing Duration Micros", "Start of Document"])?;
-    querying_writer.write_record(&["Document Count", "Query", "Query Duration Micros"])?;
-    size_writer.write_record(&["Document Count", "Mean Posting List Size", "Std Dev Posting List Size"])?;
-    final_sizes_writer.write_record(&["Term", "Posting List Size"])?;
+    let mut intersection_writer = Writer::from_path(intersection_csv_path)?;
+    let mut ranking_writer = Writer::from_path(ranking_csv_path)?;
+    let mut evaluation_writer = Writer::from_path(evaluation_csv_path)?;
+    let mut evaluation_summary_writer = Writer::from_path(evaluation_summary_csv_path)?;
+
+    indexing_writer.write_record(["Document Count", "Indexing Duration Micros", "Start of Document"])?;
+    querying_writer.write_record(["Document Count", "Query", "Query Duration Micros"])?;
+    size_writer.write_record(["Document Count", "Mean Posting List Size", "Std Dev Posting List Size"])?;
+    term_list_size_writer.write_record(["Document Count", "Term List Size"])?;
+    final_sizes_writer.write_record(["Term", "Posting List Size"])?;
+    intersection_writer.write_record(["Document Count", "Query", "Linear Duration Micros", "Skip Duration Micros"])?;
+    ranking_writer.write_record(["Document Count", "Query", "Ranked Doc Ids", "Scores"])?;
+    evaluation_writer.write_record(["Document Count", "Query", "Ground Truth Doc Id", "Rank", "Reciprocal Rank", "Precision At K", "Recall At K"])?;
+    evaluation_summary_writer.write_record(["Num Queries Evaluated", "Mean Reciprocal Rank", "Mean Precision At K", "Mean Recall At K"])?;
+
+    const RANKING_TOP_K: usize = 10;
+    let mut reciprocal_ranks: Vec<f64> = Vec::new();
+    let mut precisions_at_k: Vec<f64> = Vec::new();
+    let mut recalls_at_k: Vec<f64> = Vec::new();
 
     let mut paragraph_counter = 0;
     for filename in filenames {
@@ -57,7 +91,7 @@ pub fn benchmark_index(
             index.index_document(paragraph_counter, &paragraph);
             let indexing_duration_micros = start.elapsed().as_micros();
             let first_seven = paragraph.split_whitespace().take(7).collect::<Vec<&str>>().join(" ");
-            indexing_writer.write_record(&[&paragraph_counter.to_string(), &indexing_duration_micros.to_string(), &first_seven])?;
+            indexing_writer.write_record([paragraph_counter.to_string(), indexing_duration_micros.to_string(), first_seven])?;
 
             if paragraph_counter % query_frequency == 0 {
                 let queries = if query_token_distribution == QueryTokenDistribution::Fixed {
@@ -67,21 +101,78 @@ pub fn benchmark_index(
                     generate_queries_from_distribution(num_queries, max_query_tokens, &terms)
                 } else if query_token_distribution == QueryTokenDistribution::FromDocument {
                     pull_query_from_paragraph(&paragraph, num_queries, max_query_tokens)
+                } else if query_token_distribution == QueryTokenDistribution::Boolean {
+                    generate_boolean_queries_from_dictionary(num_queries, max_query_tokens)
                 } else {
                     panic!("Invalid query token distribution")
                 };
                 for query in queries {
                     let query_start = Instant::now();
-                    index.search(&query);
+                    if let Some(max_edits) = fuzzy_max_edits {
+                        for token in query.split_whitespace() {
+                            index.search_fuzzy_term(token, max_edits);
+                        }
+                    } else if query_token_distribution == QueryTokenDistribution::Boolean {
+                        index.search_boolean(&query);
+                    } else {
+                        index.search(&query);
+                    }
                     let query_duration_micros = query_start.elapsed().as_micros();
 
-                    querying_writer.write_record(&[&paragraph_counter.to_string(), &query.to_string(), &query_duration_micros.to_string()])?;
+                    querying_writer.write_record([paragraph_counter.to_string(), query.to_string(), query_duration_micros.to_string()])?;
+
+                    let linear_start = Instant::now();
+                    index.search_linear(&query);
+                    let linear_duration_micros = linear_start.elapsed().as_micros();
+
+                    let skip_start = Instant::now();
+                    index.search(&query);
+                    let skip_duration_micros = skip_start.elapsed().as_micros();
+
+                    intersection_writer.write_record([
+                        paragraph_counter.to_string(),
+                        query.to_string(),
+                        linear_duration_micros.to_string(),
+                        skip_duration_micros.to_string(),
+                    ])?;
+
+                    let ranked = index.search_ranked_top_k(&query, false, RANKING_TOP_K);
+                    let ranked_doc_ids = ranked.iter().map(|(doc_id, _)| doc_id.to_string()).collect::<Vec<String>>().join(";");
+                    let scores = ranked.iter().map(|(_, score)| score.to_string()).collect::<Vec<String>>().join(";");
+                    ranking_writer.write_record([paragraph_counter.to_string(), query.to_string(), ranked_doc_ids, scores])?;
+
+                    // Only queries pulled verbatim from a paragraph (`FromDocument`)
+                    // carry a known-relevant doc id (that paragraph's own), so
+                    // retrieval quality can only be scored in that mode.
+                    if query_token_distribution == QueryTokenDistribution::FromDocument {
+                        let ground_truth_doc_id = paragraph_counter;
+                        let rank = ranked.iter().position(|(doc_id, _)| *doc_id == ground_truth_doc_id).map(|i| i + 1);
+                        let reciprocal_rank = rank.map_or(0.0, |r| 1.0 / r as f64);
+                        let found = if rank.is_some() { 1.0 } else { 0.0 };
+                        let precision_at_k = found / RANKING_TOP_K as f64;
+                        let recall_at_k = found;
+
+                        reciprocal_ranks.push(reciprocal_rank);
+                        precisions_at_k.push(precision_at_k);
+                        recalls_at_k.push(recall_at_k);
+
+                        evaluation_writer.write_record([
+                            paragraph_counter.to_string(),
+                            query.to_string(),
+                            ground_truth_doc_id.to_string(),
+                            rank.map_or(String::new(), |r| r.to_string()),
+                            reciprocal_rank.to_string(),
+                            precision_at_k.to_string(),
+                            recall_at_k.to_string(),
+                        ])?;
+                    }
                 }
 
                 let posting_list_sizes = index.approximate_posting_list_sizes_in_bytes();
                 let (mean, std_dev) = compute_mean_and_std_dev(&posting_list_sizes);
 
-                size_writer.write_record(&[&paragraph_counter.to_string(), &mean.to_string(), &std_dev.to_string()])?;
+                size_writer.write_record([paragraph_counter.to_string(), mean.to_string(), std_dev.to_string()])?;
+                term_list_size_writer.write_record([paragraph_counter.to_string(), index.approximate_term_list_size_in_bytes().to_string()])?;
             }
 
             paragraph_counter += 1;
@@ -90,16 +181,52 @@ pub fn benchmark_index(
 
     let posting_list_sizes_by_term = index.approximate_posting_list_sizes_in_bytes_by_term();
     for (term, size) in posting_list_sizes_by_term {
-        final_sizes_writer.write_record(&[&term, &size.to_string()])?;
+        final_sizes_writer.write_record([term, size.to_string()])?;
     }
 
+    let (mean_reciprocal_rank, _) = compute_mean_and_std_dev_f64(&reciprocal_ranks);
+    let (mean_precision_at_k, _) = compute_mean_and_std_dev_f64(&precisions_at_k);
+    let (mean_recall_at_k, _) = compute_mean_and_std_dev_f64(&recalls_at_k);
+    evaluation_summary_writer.write_record([
+        reciprocal_ranks.len().to_string(),
+        mean_reciprocal_rank.to_string(),
+        mean_precision_at_k.to_string(),
+        mean_recall_at_k.to_string(),
+    ])?;
+
     indexing_writer.flush()?;
     querying_writer.flush()?;
     size_writer.flush()?;
+    term_list_size_writer.flush()?;
+    intersection_writer.flush()?;
+    ranking_writer.flush()?;
+    evaluation_writer.flush()?;
+    evaluation_summary_writer.flush()?;
 
     Ok(())
 }
 
+/// Like `compute_mean_and_std_dev`, but over `f64` values directly (retrieval-quality
+/// scores like reciprocal rank aren't whole-number sizes).
+fn compute_mean_and_std_dev_f64(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    let sum: f64 = values.iter().sum();
+    let mean = sum / values.len() as f64;
+
+    let variance: f64 = values.iter()
+        .map(|&value| {
+            let diff = value - mean;
+            diff * diff
+        })
+        .sum::<f64>() / values.len() as f64;
+
+    let std_dev = variance.sqrt();
+    (mean, std_dev)
+}
+
 fn compute_mean_and_std_dev(sizes: &[usize]) -> (f64, f64) {
     if sizes.is_empty() {
         return (0.0, 0.0);
@@ -196,4 +323,19 @@ mod tests {
         assert_eq!(mean, 42.0);
         assert_eq!(std_dev, 0.0);
     }
+
+    #[test]
+    fn test_mean_and_std_dev_f64_typical() {
+        let values = vec![1.0, 0.5, 0.0, 1.0];
+        let (mean, _) = compute_mean_and_std_dev_f64(&values);
+        assert_eq!(mean, 0.625);
+    }
+
+    #[test]
+    fn test_mean_and_std_dev_f64_empty() {
+        let values: Vec<f64> = vec![];
+        let (mean, std_dev) = compute_mean_and_std_dev_f64(&values);
+        assert_eq!(mean, 0.0);
+        assert_eq!(std_dev, 0.0);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,97 @@
+use std::io::{self, Read, Write};
+
+/// Magic bytes identifying the binary index format, checked on every read.
+pub(crate) const MAGIC: &[u8; 4] = b"PIIX";
+/// Current binary format version. Bump this and branch in `read_binary` if the
+/// on-disk layout ever changes in a way old readers can't parse.
+pub(crate) const VERSION: u32 = 1;
+
+/// Writes `value` as a little-endian base-128 varint: 7 value bits per byte, with
+/// the high bit set on every byte but the last. Used for doc-id and position gaps,
+/// which are usually small even when the absolute values are large.
+pub(crate) fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let mut byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+        writer.write_all(&[byte])?;
+        if value == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a varint written by `write_varint`.
+pub(crate) fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7F) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Ok(value)
+}
+
+/// Writes a length-prefixed (varint) UTF-8 string.
+pub(crate) fn write_string<W: Write>(writer: &mut W, value: &str) -> io::Result<()> {
+    write_varint(writer, value.len() as u64)?;
+    writer.write_all(value.as_bytes())
+}
+
+/// Reads a string written by `write_string`.
+pub(crate) fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let len = read_varint(reader)? as usize;
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_roundtrip_small_value() {
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 5).unwrap();
+        assert_eq!(buf.len(), 1);
+        assert_eq!(read_varint(&mut buf.as_slice()).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_varint_roundtrip_multi_byte_value() {
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 1_000_000).unwrap();
+        assert!(buf.len() > 1);
+        assert_eq!(read_varint(&mut buf.as_slice()).unwrap(), 1_000_000);
+    }
+
+    #[test]
+    fn test_varint_roundtrip_zero() {
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 0).unwrap();
+        assert_eq!(read_varint(&mut buf.as_slice()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_string_roundtrip() {
+        let mut buf = Vec::new();
+        write_string(&mut buf, "hello").unwrap();
+        assert_eq!(read_string(&mut buf.as_slice()).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_string_roundtrip_empty() {
+        let mut buf = Vec::new();
+        write_string(&mut buf, "").unwrap();
+        assert_eq!(read_string(&mut buf.as_slice()).unwrap(), "");
+    }
+}
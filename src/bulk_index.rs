@@ -0,0 +1,332 @@
+use crate::idx::PositionalInvertedIndex;
+
+use indicatif::ProgressBar;
+use serde_json::Value;
+use std::{
+    error::Error,
+    fs,
+    io::{BufRead, BufReader},
+};
+
+/// The input file formats `bulk_index` understands.
+pub enum BulkFormat {
+    Csv,
+    Json,
+    Ndjson,
+}
+
+/// Reads `input_path` as `format` and indexes each record into `index`, using
+/// `id_field` (if given) as the document ID column/key and `text_fields`
+/// (concatenated with a space) as the document's content. When `id_field` is
+/// `None`, records are assigned sequential IDs starting at 0; when it is given,
+/// a record missing that field is an error. `jobs` controls
+/// parallelism: `1` indexes records sequentially with a progress bar, while
+/// anything greater splits the parsed records into that many shards, indexes
+/// each shard in parallel via `PositionalInvertedIndex::build_parallel_from_chunks`,
+/// and merges the result into `index`. Returns the number of documents indexed.
+pub fn bulk_index(
+    index: &mut PositionalInvertedIndex,
+    input_path: &str,
+    format: BulkFormat,
+    id_field: Option<&str>,
+    text_fields: &[String],
+    jobs: usize,
+) -> Result<usize, Box<dyn Error>> {
+    let records = match format {
+        BulkFormat::Csv => read_csv_records(input_path, id_field, text_fields)?,
+        BulkFormat::Json => read_json_records(input_path, id_field, text_fields)?,
+        BulkFormat::Ndjson => read_ndjson_records(input_path, id_field, text_fields)?,
+    };
+
+    let indexed = records.len();
+    if jobs <= 1 {
+        let progress = ProgressBar::new(indexed as u64);
+        for (doc_id, content) in records {
+            index.index_document(doc_id, &content);
+            progress.inc(1);
+        }
+        progress.finish();
+    } else {
+        let chunks = shard_records(records, jobs);
+        index.merge(PositionalInvertedIndex::build_parallel_from_chunks(chunks));
+    }
+
+    Ok(indexed)
+}
+
+/// Splits `records` into up to `jobs` contiguous, disjoint chunks for
+/// `build_parallel_from_chunks`; doc IDs within each chunk stay in their
+/// original relative order, only the chunk boundaries change.
+fn shard_records(records: Vec<(usize, String)>, jobs: usize) -> Vec<Vec<(usize, String)>> {
+    let chunk_size = (records.len() as f64 / jobs as f64).ceil() as usize;
+    let chunk_size = chunk_size.max(1);
+    records.chunks(chunk_size).map(|chunk| chunk.to_vec()).collect()
+}
+
+fn read_csv_records(
+    input_path: &str,
+    id_field: Option<&str>,
+    text_fields: &[String],
+) -> Result<Vec<(usize, String)>, Box<dyn Error>> {
+    let mut reader = csv::Reader::from_path(input_path)?;
+    let headers = reader.headers()?.clone();
+
+    let id_column = id_field.map(|field| {
+        headers.iter().position(|header| header == field)
+            .ok_or_else(|| format!("id field '{}' not found in CSV headers", field))
+    }).transpose()?;
+    let text_columns: Vec<usize> = text_fields.iter()
+        .map(|field| {
+            headers.iter().position(|header| header == field)
+                .ok_or_else(|| format!("text field '{}' not found in CSV headers", field).into())
+        })
+        .collect::<Result<Vec<usize>, Box<dyn Error>>>()?;
+
+    let mut next_id = 0usize;
+    let mut records = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+
+        let doc_id = match id_column {
+            Some(column) => record.get(column)
+                .ok_or("CSV row missing id column")?
+                .parse::<usize>()?,
+            None => {
+                let id = next_id;
+                next_id += 1;
+                id
+            },
+        };
+
+        let content = text_columns.iter()
+            .filter_map(|&column| record.get(column))
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        records.push((doc_id, content));
+    }
+
+    Ok(records)
+}
+
+fn read_json_records(
+    input_path: &str,
+    id_field: Option<&str>,
+    text_fields: &[String],
+) -> Result<Vec<(usize, String)>, Box<dyn Error>> {
+    let content = fs::read_to_string(input_path)?;
+    let values: Vec<Value> = serde_json::from_str(&content)?;
+
+    let mut next_id = 0usize;
+    let mut records = Vec::with_capacity(values.len());
+    for value in &values {
+        records.push(extract_json_record(value, id_field, text_fields, &mut next_id)?);
+    }
+
+    Ok(records)
+}
+
+fn read_ndjson_records(
+    input_path: &str,
+    id_field: Option<&str>,
+    text_fields: &[String],
+) -> Result<Vec<(usize, String)>, Box<dyn Error>> {
+    let file = fs::File::open(input_path)?;
+    let reader = BufReader::new(file);
+
+    let mut next_id = 0usize;
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = serde_json::from_str(&line)?;
+        records.push(extract_json_record(&value, id_field, text_fields, &mut next_id)?);
+    }
+
+    Ok(records)
+}
+
+fn extract_json_record(
+    record: &Value,
+    id_field: Option<&str>,
+    text_fields: &[String],
+    next_id: &mut usize,
+) -> Result<(usize, String), Box<dyn Error>> {
+    let doc_id = match id_field {
+        Some(field) => {
+            let value = record.get(field).ok_or_else(|| format!("id field '{}' not found in record", field))?;
+            json_value_as_doc_id(value)?
+        },
+        None => {
+            let id = *next_id;
+            *next_id += 1;
+            id
+        },
+    };
+
+    let content = text_fields.iter()
+        .filter_map(|field| record.get(field))
+        .map(json_value_as_text)
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    Ok((doc_id, content))
+}
+
+fn json_value_as_doc_id(value: &Value) -> Result<usize, Box<dyn Error>> {
+    match value {
+        Value::Number(n) => n.as_u64().map(|n| n as usize).ok_or_else(|| "id field is not a non-negative integer".into()),
+        Value::String(s) => Ok(s.parse::<usize>()?),
+        _ => Err("id field must be a number or a numeric string".into()),
+    }
+}
+
+fn json_value_as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_bulk_index_csv_with_explicit_id_field() {
+        let path = write_temp_file(
+            "piix_test_bulk_index.csv",
+            "id,title,body\n1,hello,world\n2,goodbye,moon\n",
+        );
+        let mut index = PositionalInvertedIndex::new();
+        let count = bulk_index(
+            &mut index,
+            path.to_str().unwrap(),
+            BulkFormat::Csv,
+            Some("id"),
+            &["title".to_string(), "body".to_string()],
+            1,
+        ).unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(index.search("hello world"), vec![1]);
+        assert_eq!(index.search("goodbye moon"), vec![2]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_bulk_index_csv_auto_assigns_sequential_ids() {
+        let path = write_temp_file(
+            "piix_test_bulk_index_autoid.csv",
+            "title\nhello world\ngoodbye moon\n",
+        );
+        let mut index = PositionalInvertedIndex::new();
+        let count = bulk_index(
+            &mut index,
+            path.to_str().unwrap(),
+            BulkFormat::Csv,
+            None,
+            &["title".to_string()],
+            1,
+        ).unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(index.search("hello world"), vec![0]);
+        assert_eq!(index.search("goodbye moon"), vec![1]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_bulk_index_ndjson_concatenates_text_fields() {
+        let path = write_temp_file(
+            "piix_test_bulk_index.ndjson",
+            "{\"id\": 1, \"title\": \"hello\", \"body\": \"world\"}\n{\"id\": 2, \"title\": \"goodbye\", \"body\": \"moon\"}\n",
+        );
+        let mut index = PositionalInvertedIndex::new();
+        let count = bulk_index(
+            &mut index,
+            path.to_str().unwrap(),
+            BulkFormat::Ndjson,
+            Some("id"),
+            &["title".to_string(), "body".to_string()],
+            1,
+        ).unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(index.search("hello world"), vec![1]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_bulk_index_json_array_of_objects() {
+        let path = write_temp_file(
+            "piix_test_bulk_index.json",
+            "[{\"id\": 1, \"title\": \"hello world\"}, {\"id\": 2, \"title\": \"goodbye moon\"}]",
+        );
+        let mut index = PositionalInvertedIndex::new();
+        let count = bulk_index(
+            &mut index,
+            path.to_str().unwrap(),
+            BulkFormat::Json,
+            Some("id"),
+            &["title".to_string()],
+            1,
+        ).unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(index.search("hello world"), vec![1]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_bulk_index_csv_missing_id_field_errors() {
+        let path = write_temp_file(
+            "piix_test_bulk_index_missing_id.csv",
+            "title\nhello world\n",
+        );
+        let mut index = PositionalInvertedIndex::new();
+        let result = bulk_index(
+            &mut index,
+            path.to_str().unwrap(),
+            BulkFormat::Csv,
+            Some("id"),
+            &["title".to_string()],
+            1,
+        );
+
+        assert!(result.is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_bulk_index_csv_with_multiple_jobs_matches_sequential() {
+        let path = write_temp_file(
+            "piix_test_bulk_index_parallel.csv",
+            "id,title\n1,hello world\n2,goodbye moon\n3,rust programming\n4,parallel indexing\n",
+        );
+        let mut index = PositionalInvertedIndex::new();
+        let count = bulk_index(
+            &mut index,
+            path.to_str().unwrap(),
+            BulkFormat::Csv,
+            Some("id"),
+            &["title".to_string()],
+            2,
+        ).unwrap();
+
+        assert_eq!(count, 4);
+        assert_eq!(index.search("hello world"), vec![1]);
+        assert_eq!(index.search("goodbye moon"), vec![2]);
+        assert_eq!(index.search("rust programming"), vec![3]);
+        assert_eq!(index.search("parallel indexing"), vec![4]);
+        fs::remove_file(path).unwrap();
+    }
+}
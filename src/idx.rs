@@ -1,57 +1,447 @@
+use indicatif::ProgressBar;
 use rand::{
     distributions::{Distribution, WeightedIndex},
     thread_rng
 };
+use rayon::prelude::*;
 use serde::{
     Serialize,
     Deserialize
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Read, Write},
     mem
 };
 
+use crate::binary::{self, MAGIC, VERSION};
+use crate::query::Operation;
+use crate::roaring::RoaringBitmap;
+
 #[derive(Serialize, Deserialize)]
 pub enum TokenOrdering {
     TokenOrder,
     AscendingFrequencyOrder,
 }
 
+/// A cursor over a sorted sequence of document IDs supporting forward-only
+/// traversal and skip-ahead search, used to leapfrog-intersect posting lists
+/// without scanning every entry.
+trait DocSet {
+    /// Moves to and returns the next document ID, or `None` if exhausted.
+    fn advance(&mut self) -> Option<usize>;
+    /// Moves to the first document ID `>= target`, or `None` if exhausted.
+    fn seek(&mut self, target: usize) -> Option<usize>;
+}
+
+/// A `(doc_id, index)` checkpoint recorded every `stride` entries of a posting
+/// list's doc-id slice, so `seek` can binary-jump to the right neighborhood
+/// instead of exponentially probing from the cursor's current position.
+struct SkipCheckpoint {
+    doc_id: usize,
+    index: usize,
+}
+
+/// Builds one checkpoint roughly every `sqrt(doc_ids.len())` entries (at least
+/// every entry for tiny lists), trading a little extra memory for an O(sqrt(n))
+/// coarse jump followed by a narrow binary search instead of a linear scan.
+fn build_skip_checkpoints(doc_ids: &[usize]) -> Vec<SkipCheckpoint> {
+    let stride = (doc_ids.len() as f64).sqrt().ceil() as usize;
+    let stride = stride.max(1);
+    doc_ids.iter()
+        .enumerate()
+        .step_by(stride)
+        .map(|(index, &doc_id)| SkipCheckpoint { doc_id, index })
+        .collect()
+}
+
+struct DocIdCursor<'a> {
+    doc_ids: &'a [usize],
+    idx: usize,
+    checkpoints: Vec<SkipCheckpoint>,
+}
+
+impl<'a> DocIdCursor<'a> {
+    fn new(doc_ids: &'a [usize]) -> Self {
+        let checkpoints = build_skip_checkpoints(doc_ids);
+        DocIdCursor { doc_ids, idx: 0, checkpoints }
+    }
+}
+
+impl<'a> DocSet for DocIdCursor<'a> {
+    fn advance(&mut self) -> Option<usize> {
+        let doc_id = self.doc_ids.get(self.idx).copied();
+        if doc_id.is_some() {
+            self.idx += 1;
+        }
+        doc_id
+    }
+
+    /// Moves to the first doc id `>= target`. If that doc id is an exact match
+    /// for `target`, it's considered consumed and `idx` is advanced past it, so a
+    /// following `advance()` yields the *next* doc id rather than re-emitting the
+    /// one `seek` just returned. A non-exact landing (the next larger doc id,
+    /// found after a gap) leaves `idx` pointing at it, unconsumed, since the
+    /// leapfrog intersection in `intersect_doc_ids` may still need to compare it
+    /// against a smaller target on a later call.
+    fn seek(&mut self, target: usize) -> Option<usize> {
+        if self.idx < self.doc_ids.len() && self.doc_ids[self.idx] < target {
+            // Binary-jump over the checkpoints to find the last one not past
+            // `target`, then binary search within the (at most one stride wide)
+            // span that follows it, so skip-ahead stays sub-linear instead of
+            // scanning one by one.
+            let checkpoint_idx = self.checkpoints.partition_point(|c| c.doc_id < target);
+            let lo = self.checkpoints.get(checkpoint_idx.saturating_sub(1))
+                .map(|c| c.index)
+                .unwrap_or(self.idx)
+                .max(self.idx);
+            let hi = self.checkpoints.get(checkpoint_idx)
+                .map(|c| c.index + 1)
+                .unwrap_or(self.doc_ids.len());
+
+            let offset = self.doc_ids[lo..hi].partition_point(|&d| d < target);
+            self.idx = lo + offset;
+        }
+
+        let found = self.doc_ids.get(self.idx).copied();
+        if found == Some(target) {
+            self.idx += 1;
+        }
+        found
+    }
+}
+
+/// A `DocSet` that scans one entry at a time with no skip checkpoints, kept
+/// alongside `DocIdCursor` purely as the "linear" baseline `search_linear`
+/// benchmarks against to quantify what the skip checkpoints buy `seek`.
+struct LinearDocIdCursor<'a> {
+    doc_ids: &'a [usize],
+    idx: usize,
+}
+
+impl<'a> LinearDocIdCursor<'a> {
+    fn new(doc_ids: &'a [usize]) -> Self {
+        LinearDocIdCursor { doc_ids, idx: 0 }
+    }
+}
+
+impl<'a> DocSet for LinearDocIdCursor<'a> {
+    fn advance(&mut self) -> Option<usize> {
+        let doc_id = self.doc_ids.get(self.idx).copied();
+        if doc_id.is_some() {
+            self.idx += 1;
+        }
+        doc_id
+    }
+
+    /// Same consumed-on-exact-match contract as `DocIdCursor::seek` (see its doc
+    /// comment), just walked one entry at a time instead of via skip checkpoints.
+    fn seek(&mut self, target: usize) -> Option<usize> {
+        while let Some(&doc_id) = self.doc_ids.get(self.idx) {
+            if doc_id < target {
+                self.idx += 1;
+                continue;
+            }
+            if doc_id == target {
+                self.idx += 1;
+            }
+            return Some(doc_id);
+        }
+        None
+    }
+}
+
+/// Turns a raw, case-folded token (as produced by `get_tokens_with_offsets`) into
+/// the term actually stored in postings and looked up by queries, or drops it
+/// entirely by returning `None`. Pluggable via `PositionalInvertedIndex::with_analyzer`
+/// so index size and query behavior can be compared with and without stemming/stop-word
+/// removal; governs `index_document` and the core `search`/`search_boolean`/
+/// `search_ranked` paths.
+pub trait Analyzer: Send + Sync {
+    fn process_token(&self, token: &str) -> Option<String>;
+}
+
+/// The default analyzer: every case-folded token is kept as-is, with no stop-word
+/// removal or stemming. Matches the index's historical, pre-analyzer behavior.
+#[derive(Default)]
+pub struct StandardAnalyzer;
+
+impl Analyzer for StandardAnalyzer {
+    fn process_token(&self, token: &str) -> Option<String> {
+        Some(token.to_string())
+    }
+}
+
+/// Drops a small built-in English stop-word list (if `remove_stop_words`) and
+/// reduces every remaining token to a stem with `stem`, so inflected forms of the
+/// same word (`running`/`runs`/`run`) collapse to one indexed term.
+pub struct StemmingAnalyzer {
+    pub remove_stop_words: bool,
+}
+
+impl StemmingAnalyzer {
+    pub fn new(remove_stop_words: bool) -> Self {
+        StemmingAnalyzer { remove_stop_words }
+    }
+}
+
+impl Analyzer for StemmingAnalyzer {
+    fn process_token(&self, token: &str) -> Option<String> {
+        if self.remove_stop_words && is_stop_word(token) {
+            return None;
+        }
+        Some(stem(token))
+    }
+}
+
+const STOP_WORDS: [&str; 16] = [
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from",
+    "in", "is", "it", "of", "on", "the",
+];
+
+fn is_stop_word(token: &str) -> bool {
+    STOP_WORDS.contains(&token)
+}
+
+/// A simplified Porter/Snowball-style stemmer: strips the longest matching common
+/// English suffix (leaving at least a 2-character stem) and undoes a doubled final
+/// consonant left behind (`running` -> `runn` -> `run`). Not a full implementation
+/// of Porter's vowel/consonant measure rules, but collapses the inflections that
+/// matter most for recall.
+fn stem(token: &str) -> String {
+    const SUFFIXES: [&str; 13] = [
+        "ization", "ational", "fulness", "ousness", "iveness",
+        "edly", "ing", "ies", "ied", "es", "ed", "ly", "s",
+    ];
+
+    for suffix in SUFFIXES {
+        if token.len() > suffix.len() + 1 && token.ends_with(suffix) {
+            return undouble_final_consonant(&token[..token.len() - suffix.len()]);
+        }
+    }
+
+    token.to_string()
+}
+
+fn undouble_final_consonant(stem: &str) -> String {
+    let chars: Vec<char> = stem.chars().collect();
+    if chars.len() >= 3 {
+        let last = chars[chars.len() - 1];
+        let second_last = chars[chars.len() - 2];
+        if last == second_last && !"aeiou".contains(last) {
+            return chars[..chars.len() - 1].iter().collect();
+        }
+    }
+    chars.into_iter().collect()
+}
+
+fn default_analyzer() -> Box<dyn Analyzer> {
+    Box::new(StandardAnalyzer)
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct PositionalInvertedIndex {
     index: HashMap<String, HashMap<usize, Vec<usize>>>,
+    /// Each term's document set, mirrored as a compressed roaring bitmap. This is
+    /// what `intersect_doc_ids` leapfrogs over, and what a future boolean layer could
+    /// AND/OR/ANDNOT to compute candidate doc-id universes before touching positions.
+    doc_bitmaps: HashMap<String, RoaringBitmap>,
     term_frequencies: HashMap<String, usize>,
     ordering: TokenOrdering,
+    doc_lengths: HashMap<usize, usize>,
+    /// Bidirectional token equivalences: a token maps to every other token sequence
+    /// it was registered as a synonym of, e.g. `"nyc" -> [["new", "york"]]`.
+    synonyms: HashMap<String, Vec<Vec<String>>>,
+    /// Per document, the `(start, end)` character span of the token at each ordinal
+    /// position, so a matched position can be mapped back to a slice of the original
+    /// text for highlighting.
+    doc_token_offsets: HashMap<usize, Vec<(usize, usize)>>,
+    /// Post-tokenization filter/transform stage (stop-word removal, stemming, ...)
+    /// applied by `index_document` and by `search`/`search_ranked`/`search_boolean`'s
+    /// term and phrase leaves. Not serialized: on deserialization the index falls
+    /// back to `StandardAnalyzer`, matching the index's pre-analyzer behavior.
+    #[serde(skip, default = "default_analyzer")]
+    analyzer: Box<dyn Analyzer>,
 }
 
 impl PositionalInvertedIndex {
     pub fn new() -> Self {
         PositionalInvertedIndex {
             index: HashMap::new(),
+            doc_bitmaps: HashMap::new(),
             term_frequencies: HashMap::new(),
             ordering: TokenOrdering::TokenOrder,
+            doc_lengths: HashMap::new(),
+            synonyms: HashMap::new(),
+            doc_token_offsets: HashMap::new(),
+            analyzer: default_analyzer(),
         }
     }
 
     pub fn with_ordering(ordering: TokenOrdering) -> Self {
         PositionalInvertedIndex {
             index: HashMap::new(),
+            doc_bitmaps: HashMap::new(),
             term_frequencies: HashMap::new(),
             ordering: ordering,
+            doc_lengths: HashMap::new(),
+            synonyms: HashMap::new(),
+            doc_token_offsets: HashMap::new(),
+            analyzer: default_analyzer(),
+        }
+    }
+
+    /// Builds an index that routes every indexed and queried token through
+    /// `analyzer` instead of the default identity pass-through, so callers can
+    /// compare index size and query behavior with and without stemming/stop-word
+    /// removal (e.g. `with_analyzer(Box::new(StemmingAnalyzer::new(true)))`).
+    pub fn with_analyzer(analyzer: Box<dyn Analyzer>) -> Self {
+        PositionalInvertedIndex { analyzer, ..Self::new() }
+    }
+
+    /// Changes the token search ordering after construction, so callers that also
+    /// need a non-default `analyzer` (via `with_analyzer`) aren't forced to choose
+    /// between the two constructors.
+    pub fn set_ordering(&mut self, ordering: TokenOrdering) {
+        self.ordering = ordering;
+    }
+
+    /// Registers `terms` as mutual synonyms: searching for any one of them will also
+    /// match the others. Each term is tokenized and keyed by its first token, so a
+    /// multi-word term (e.g. `"new york"`) can stand in for a single-word one (e.g.
+    /// `"nyc"`) and vice versa.
+    pub fn add_synonyms(&mut self, terms: &[&str]) {
+        let sequences: Vec<Vec<String>> = terms.iter()
+            .map(|term| Self::get_tokens(term))
+            .filter(|sequence| !sequence.is_empty())
+            .collect();
+
+        for (i, sequence) in sequences.iter().enumerate() {
+            let entry = self.synonyms.entry(sequence[0].clone()).or_insert_with(Vec::new);
+            for (j, other) in sequences.iter().enumerate() {
+                if i != j && !entry.contains(other) {
+                    entry.push(other.clone());
+                }
+            }
         }
     }
 
     pub fn index_document(&mut self, doc_id: usize, content: &str) {
-        let tokens = Self::get_tokens(content);
-        for (pos, token) in tokens.iter().enumerate() {
+        let tokens = Self::get_tokens_with_offsets(content);
+        let mut offsets = Vec::with_capacity(tokens.len());
+        for (token, start, end) in tokens.iter() {
+            let term = match self.analyzer.process_token(token) {
+                Some(term) => term,
+                None => continue,
+            };
+            // The position ordinal is the kept-token count so far, not the raw
+            // token index, so postings stay aligned with `doc_token_offsets`
+            // once the analyzer has dropped some tokens (e.g. stop words).
+            let pos = offsets.len();
             self.index
-                .entry(token.clone())
+                .entry(term.clone())
                 .or_default()
                 .entry(doc_id)
                 .or_default()
                 .push(pos);
-            *self.term_frequencies.entry(token.clone()).or_insert(0) += 1;
+            *self.term_frequencies.entry(term.clone()).or_insert(0) += 1;
+            self.doc_bitmaps.entry(term).or_insert_with(RoaringBitmap::new).insert(doc_id);
+            offsets.push((*start, *end));
+        }
+        *self.doc_lengths.entry(doc_id).or_insert(0) += offsets.len();
+        self.doc_token_offsets.insert(doc_id, offsets);
+    }
+
+    /// Tokenizes `content` and routes each token through `self.analyzer`, dropping
+    /// any the analyzer filters out. Used by `search`/`search_ranked` so query-side
+    /// tokens are analyzed the same way as `index_document`'s (`evaluate` applies
+    /// the analyzer to its term and phrase leaves itself).
+    fn analyzed_tokens(&self, content: &str) -> Vec<String> {
+        Self::get_tokens(content)
+            .into_iter()
+            .filter_map(|token| self.analyzer.process_token(&token))
+            .collect()
+    }
+
+    /// Builds an index from a multi-file corpus in parallel: each file is split into
+    /// blank-line-delimited paragraphs (one document each), assigned a disjoint,
+    /// contiguous doc-ID range up front, and indexed into its own shard on a rayon
+    /// worker. Shards are then merged in file order. Because the ranges never
+    /// overlap, merging is a plain union of each term's postings rather than a
+    /// full re-sort. Shows an indicatif progress bar over files as shards complete.
+    pub fn build_parallel(files: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        let file_paragraphs: Vec<Vec<String>> = files.iter()
+            .map(|filename| -> Result<Vec<String>, Box<dyn std::error::Error>> {
+                let contents = fs::read_to_string(filename)?;
+                let re = regex::Regex::new(r"\n\s*\n")?;
+                Ok(re.split(&contents)
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string())
+                    .collect())
+            })
+            .collect::<Result<Vec<Vec<String>>, Box<dyn std::error::Error>>>()?;
+
+        let mut next_doc_id = 0usize;
+        let chunks: Vec<Vec<(usize, String)>> = file_paragraphs.into_iter()
+            .map(|paragraphs| {
+                let chunk: Vec<(usize, String)> = paragraphs.into_iter()
+                    .enumerate()
+                    .map(|(i, content)| (next_doc_id + i, content))
+                    .collect();
+                next_doc_id += chunk.len();
+                chunk
+            })
+            .collect();
+
+        Ok(Self::build_parallel_from_chunks(chunks))
+    }
+
+    /// Builds an index from pre-assigned `(doc_id, content)` chunks in parallel, one
+    /// shard per chunk, then merges the shards. Callers are responsible for ensuring
+    /// doc IDs are disjoint across chunks; see `build_parallel`.
+    pub(crate) fn build_parallel_from_chunks(chunks: Vec<Vec<(usize, String)>>) -> Self {
+        let progress = ProgressBar::new(chunks.len() as u64);
+        let shards: Vec<PositionalInvertedIndex> = chunks.into_par_iter()
+            .map(|chunk| {
+                let mut shard = PositionalInvertedIndex::new();
+                for (doc_id, content) in chunk {
+                    shard.index_document(doc_id, &content);
+                }
+                progress.inc(1);
+                shard
+            })
+            .collect();
+        progress.finish();
+
+        let mut merged = PositionalInvertedIndex::new();
+        for shard in shards {
+            merged.merge(shard);
+        }
+        merged
+    }
+
+    /// Merges `other`'s postings, term frequencies, doc lengths, and token offsets
+    /// into `self`. Assumes `other` was built over a disjoint set of doc IDs (as
+    /// `build_parallel_from_chunks` guarantees), so per-term postings, doc lengths,
+    /// and token offsets can be unioned directly with no doc-ID collisions to
+    /// reconcile; only the shared roaring bitmaps and summed term frequencies
+    /// actually need combining logic.
+    pub(crate) fn merge(&mut self, other: PositionalInvertedIndex) {
+        for (term, postings) in other.index {
+            self.index.entry(term).or_default().extend(postings);
+        }
+        for (term, bitmap) in other.doc_bitmaps {
+            self.doc_bitmaps.entry(term)
+                .and_modify(|existing| *existing = existing.union(&bitmap))
+                .or_insert(bitmap);
         }
+        for (term, frequency) in other.term_frequencies {
+            *self.term_frequencies.entry(term).or_insert(0) += frequency;
+        }
+        self.doc_lengths.extend(other.doc_lengths);
+        self.doc_token_offsets.extend(other.doc_token_offsets);
     }
 
     pub fn search(&self, query: &str) -> Vec<usize> {
@@ -59,16 +449,81 @@ impl PositionalInvertedIndex {
             return vec![];
         }
 
-        let tokens = Self::get_tokens(query);
+        let tokens = self.analyzed_tokens(query);
         let tokens = self.order_tokens(&tokens);
         if tokens.is_empty() {
             return vec![];
         }
 
+        self.phrase_match(&tokens)
+    }
+
+    /// Evaluates a boolean query tree (AND/OR/NOT over terms and quoted phrases)
+    /// against the index. Term and phrase leaves produce candidate doc-id sets
+    /// (phrases via the same positional matching `search` uses), `And` intersects
+    /// those sets, `Or` unions them, and `Not` subtracts from every indexed document.
+    pub fn search_boolean(&self, query: &str) -> Vec<usize> {
+        let operation = crate::query::parse_query(query);
+        let mut results: Vec<usize> = self.evaluate(&operation).into_iter().collect();
+        results.sort();
+        results
+    }
+
+    fn evaluate(&self, operation: &Operation) -> HashSet<usize> {
+        match operation {
+            Operation::Term(term) => {
+                match self.analyzer.process_token(term) {
+                    Some(term) => self.doc_bitmaps.get(&term).map_or_else(HashSet::new, |bitmap| bitmap.iter().collect()),
+                    None => HashSet::new(),
+                }
+            },
+            Operation::Phrase(tokens) => {
+                let tokens: Vec<String> = tokens.iter().filter_map(|token| self.analyzer.process_token(token)).collect();
+                self.phrase_match(&tokens).into_iter().collect()
+            },
+            Operation::And(operations) => {
+                let mut sets = operations.iter().map(|op| self.evaluate(op));
+                match sets.next() {
+                    Some(first) => sets.fold(first, |acc, set| acc.intersection(&set).copied().collect()),
+                    None => HashSet::new(),
+                }
+            },
+            Operation::Or(operations) => {
+                operations.iter().fold(HashSet::new(), |acc, op| acc.union(&self.evaluate(op)).copied().collect())
+            },
+            Operation::Not(inner) => {
+                let excluded = self.evaluate(inner);
+                self.doc_lengths.keys().copied().filter(|doc_id| !excluded.contains(doc_id)).collect()
+            },
+        }
+    }
+
+    /// The positional phrase match underlying `search`: a document matches if every
+    /// token appears, in order, at consecutive positions. Takes already-tokenized
+    /// input so it can be reused directly as the evaluation of a `Phrase` leaf in a
+    /// boolean query tree.
+    fn phrase_match(&self, tokens: &[String]) -> Vec<usize> {
+        if tokens.is_empty() {
+            return vec![];
+        }
+
+        // Prune down to documents containing every token before doing the more
+        // expensive positional alignment below, via a skip-checkpoint leapfrog over
+        // the rarest term's doc IDs rather than materializing full posting lists.
+        let candidate_docs = match self.intersect_doc_ids(tokens) {
+            Some(docs) => docs,
+            None => return vec![],
+        };
+        if candidate_docs.is_empty() {
+            return vec![];
+        }
+
         let mut possibles: HashMap<usize, Vec<usize>> = HashMap::new();
         if let Some(docs) = self.index.get(&tokens[0]) {
-            for (&doc_id, positions) in docs {
-                possibles.insert(doc_id, positions.clone());
+            for &doc_id in &candidate_docs {
+                if let Some(positions) = docs.get(&doc_id) {
+                    possibles.insert(doc_id, positions.clone());
+                }
             }
         } else {
             return vec![];
@@ -105,400 +560,2063 @@ impl PositionalInvertedIndex {
         results
     }
 
-    fn get_tokens(content: &str) -> Vec<String> {
-        content.split_whitespace()
-            .map(|s| s.chars()
-                    .filter(|c| c.is_alphanumeric())
-                    .collect::<String>()
-                    .to_lowercase())
-            .filter(|s| !s.is_empty())
-            .collect::<Vec<_>>()
+    /// Matches `terms` (each tokenized and concatenated in order) as an exact,
+    /// adjacent phrase: documents where every term appears, in order, at
+    /// consecutive positions. A thin public entry point onto `phrase_match` that
+    /// takes raw term strings instead of already-tokenized input, for query
+    /// syntax like `"exact phrase"` that bypasses the boolean query tree.
+    pub fn search_phrase(&self, terms: &[&str]) -> Vec<usize> {
+        let tokens: Vec<String> = terms.iter().flat_map(|term| Self::get_tokens(term)).collect();
+        self.phrase_match(&tokens)
     }
 
-    fn order_tokens(&self, tokens: &Vec<String>) -> Vec<String> {
-        match self.ordering {
-            TokenOrdering::TokenOrder => tokens.clone(),
-            TokenOrdering::AscendingFrequencyOrder => {
-                let mut token_freq_pairs: Vec<(&String, &usize)> = tokens.iter()
-                    .map(|t| (t, self.term_frequencies.get(t).unwrap_or(&0)))
-                    .collect();
-                
-                token_freq_pairs.sort_by_key(|&(_, freq)| freq);
-                token_freq_pairs.into_iter().map(|(token, _)| token.clone()).collect()
-            },
+    /// Matches `terms` (each tokenized and concatenated in order) as a proximity
+    /// query: documents containing every term, where each adjacent pair in
+    /// `terms` has *some* occurrence within `k` positions of each other, in
+    /// either direction (unlike `search_phrase`, order and adjacency within a
+    /// term aren't required). Backs the `term1 NEAR/k term2` query syntax.
+    pub fn search_proximity(&self, terms: &[&str], k: usize) -> Vec<usize> {
+        let tokens: Vec<String> = terms.iter().flat_map(|term| Self::get_tokens(term)).collect();
+        if tokens.len() < 2 {
+            return self.phrase_match(&tokens);
+        }
+
+        let candidate_docs = match self.intersect_doc_ids(&tokens) {
+            Some(docs) => docs,
+            None => return vec![],
+        };
+
+        let mut results = vec![];
+        'doc: for doc_id in candidate_docs {
+            for pair in tokens.windows(2) {
+                let positions_a = match self.index.get(&pair[0]).and_then(|docs| docs.get(&doc_id)) {
+                    Some(positions) => positions,
+                    None => continue 'doc,
+                };
+                let positions_b = match self.index.get(&pair[1]).and_then(|docs| docs.get(&doc_id)) {
+                    Some(positions) => positions,
+                    None => continue 'doc,
+                };
+                if !Self::some_pair_within(positions_a, positions_b, k) {
+                    continue 'doc;
+                }
+            }
+            results.push(doc_id);
         }
+        results
     }
 
-    pub fn get_random_terms(&self, n: usize) -> HashMap<String, usize> {
-        let mut random_terms = HashMap::new();
-    
-        if self.term_frequencies.is_empty() || n == 0 {
-            return random_terms;
+    /// Whether some position in `a` and some position in `b` (both sorted
+    /// ascending) differ by at most `k`, found via a two-pointer sweep rather
+    /// than comparing every pair.
+    fn some_pair_within(a: &[usize], b: &[usize], k: usize) -> bool {
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            let diff = a[i].abs_diff(b[j]);
+            if diff <= k {
+                return true;
+            }
+            if a[i] < b[j] {
+                i += 1;
+            } else {
+                j += 1;
+            }
         }
-    
-        let mut rng = thread_rng();
-        let terms: Vec<&String> = self.term_frequencies.keys().collect();
-        let weights: Vec<&usize> = self.term_frequencies.values().collect();
-    
-        let dist = WeightedIndex::new(weights).unwrap();
-    
-        while random_terms.len() < n && random_terms.len() < self.term_frequencies.len() {
-            let term = terms[dist.sample(&mut rng)].clone();
-            *random_terms.entry(term.to_string()).or_insert(0) = self.term_frequencies[&term];
+        false
+    }
+
+    /// Like `search`, but also returns the character span of each matched token in
+    /// the original document text, for rendering highlighted snippets. Built on the
+    /// same phrase-alignment logic as `search`, just retaining the match positions
+    /// instead of discarding them.
+    pub fn search_with_matches(&self, query: &str) -> Vec<(usize, Vec<(usize, usize)>)> {
+        if query.is_empty() {
+            return vec![];
         }
 
-        random_terms
+        let tokens = Self::get_tokens(query);
+        let tokens = self.order_tokens(&tokens);
+        if tokens.is_empty() {
+            return vec![];
+        }
+
+        let matching_docs = self.phrase_match(&tokens);
+        let empty_offsets = Vec::new();
+
+        matching_docs.into_iter()
+            .map(|doc_id| {
+                let offsets = self.doc_token_offsets.get(&doc_id).unwrap_or(&empty_offsets);
+                let mut spans: Vec<(usize, usize)> = self.phrase_starts(&tokens, doc_id).into_iter()
+                    .flat_map(|start| (start..start + tokens.len()).filter_map(|pos| offsets.get(pos).copied()))
+                    .collect();
+                spans.sort();
+                spans.dedup();
+                (doc_id, spans)
+            })
+            .collect()
     }
 
-    pub fn approximate_term_list_size_in_bytes(&self) -> usize {
-        // Average English word is length 4.
-        let term_list_size = std::mem::size_of_val(&self.index) + &self.index.len() * (mem::size_of::<String>()+4);
-        let term_frequency_list_size = std::mem::size_of_val(&self.term_frequencies) + &self.term_frequencies.len() * (mem::size_of::<String>()+mem::size_of::<usize>());
-        return term_list_size + term_frequency_list_size;
+    /// Ranks matching documents by BM25 relevance instead of the doc-id order `search`
+    /// returns. When `require_phrase` is true, candidates are restricted to documents
+    /// satisfying the same positional phrase match as `search`; when false, any
+    /// document containing at least one query term is scored, giving a bag-of-words
+    /// ranking. Returns `(doc_id, score)` pairs sorted by descending score.
+    pub fn search_ranked(&self, query: &str, require_phrase: bool) -> Vec<(usize, f32)> {
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let tokens = self.analyzed_tokens(query);
+        if tokens.is_empty() {
+            return vec![];
+        }
+
+        let candidate_docs: Vec<usize> = if require_phrase {
+            self.search(query)
+        } else {
+            let mut docs: HashMap<usize, ()> = HashMap::new();
+            for token in &tokens {
+                if let Some(posting_list) = self.index.get(token) {
+                    for &doc_id in posting_list.keys() {
+                        docs.insert(doc_id, ());
+                    }
+                }
+            }
+            let mut docs: Vec<usize> = docs.into_keys().collect();
+            docs.sort();
+            docs
+        };
+
+        if candidate_docs.is_empty() {
+            return vec![];
+        }
+
+        let doc_count = self.doc_lengths.len() as f32;
+        let avg_doc_len = if self.doc_lengths.is_empty() {
+            0.0
+        } else {
+            self.doc_lengths.values().sum::<usize>() as f32 / doc_count
+        };
+
+        const K1: f32 = 1.2;
+        const B: f32 = 0.75;
+
+        let mut scored: Vec<(usize, f32)> = candidate_docs.into_iter()
+            .map(|doc_id| {
+                let doc_len = *self.doc_lengths.get(&doc_id).unwrap_or(&0) as f32;
+                let mut score = 0.0;
+                for token in &tokens {
+                    if let Some(posting_list) = self.index.get(token) {
+                        if let Some(positions) = posting_list.get(&doc_id) {
+                            let df = posting_list.len() as f32;
+                            let tf = positions.len() as f32;
+                            let idf = ((doc_count - df + 0.5) / (df + 0.5) + 1.0).ln();
+                            let denom = tf + K1 * (1.0 - B + B * doc_len / avg_doc_len.max(1.0));
+                            score += idf * (tf * (K1 + 1.0)) / denom;
+                        }
+                    }
+                }
+                (doc_id, score)
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored
     }
 
-    pub fn approximate_posting_list_sizes_in_bytes(&self) -> Vec<usize> {
-        let mut sizes = vec![];
-        for (_term, posting_list) in &self.index {
-            let mut size = 0;
-            for (_doc_id, positions) in posting_list {
-                // Add 1 to account for the doc ID.
-                size += (positions.len() + 1) * mem::size_of::<usize>();
+    /// `search_ranked`, truncated to the `top_k` highest-scoring documents. A thin
+    /// wrapper rather than a change to `search_ranked` itself, since `top_k` is
+    /// unrelated to (and would collide in position with) that method's existing
+    /// `require_phrase` parameter.
+    pub fn search_ranked_top_k(&self, query: &str, require_phrase: bool, top_k: usize) -> Vec<(usize, f32)> {
+        let mut results = self.search_ranked(query, require_phrase);
+        results.truncate(top_k);
+        results
+    }
+
+    /// Like `search`, but tolerant of gaps between query tokens: a document matches if
+    /// its tokens appear in order with at most `slop` intervening positions between
+    /// consecutive tokens. Results are ranked by tightness, ascending by the summed
+    /// gap across the best alignment found in each document, so phrases with zero
+    /// gaps (an exact adjacent match) sort first.
+    pub fn search_with_slop(&self, query: &str, slop: usize) -> Vec<(usize, usize)> {
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let tokens = Self::get_tokens(query);
+        let tokens = self.order_tokens(&tokens);
+        if tokens.is_empty() {
+            return vec![];
+        }
+
+        // Per document, the lowest accumulated gap cost reachable by ending the
+        // phrase-so-far at a given position.
+        let mut frontiers: HashMap<usize, HashMap<usize, usize>> = HashMap::new();
+        if let Some(docs) = self.index.get(&tokens[0]) {
+            for (&doc_id, positions) in docs {
+                let ends = positions.iter().map(|&p| (p, 0usize)).collect();
+                frontiers.insert(doc_id, ends);
+            }
+        } else {
+            return vec![];
+        }
+
+        for token in tokens.iter().skip(1) {
+            let posting_list = match self.index.get(token) {
+                Some(posting_list) => posting_list,
+                None => return vec![],
+            };
+
+            let mut new_frontiers: HashMap<usize, HashMap<usize, usize>> = HashMap::new();
+            for (&doc_id, ends) in frontiers.iter() {
+                if let Some(positions) = posting_list.get(&doc_id) {
+                    let mut new_ends: HashMap<usize, usize> = HashMap::new();
+                    for (&prev_pos, &cost) in ends.iter() {
+                        let lower = prev_pos + 1;
+                        let upper = prev_pos + 1 + slop;
+                        let start_idx = positions.partition_point(|&p| p < lower);
+                        for &p in &positions[start_idx..] {
+                            if p > upper {
+                                break;
+                            }
+                            let new_cost = cost + (p - lower);
+                            new_ends.entry(p)
+                                .and_modify(|existing| *existing = (*existing).min(new_cost))
+                                .or_insert(new_cost);
+                        }
+                    }
+                    if !new_ends.is_empty() {
+                        new_frontiers.insert(doc_id, new_ends);
+                    }
+                }
+            }
+            frontiers = new_frontiers;
+            if frontiers.is_empty() {
+                return vec![];
             }
-            sizes.push(size);
         }
-        sizes.sort();
-        sizes
+
+        let mut results: Vec<(usize, usize)> = frontiers.into_iter()
+            .map(|(doc_id, ends)| (doc_id, ends.values().copied().min().unwrap_or(0)))
+            .collect();
+        results.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
+        results
+    }
+
+    /// Performs the same positional phrase match as `search`, but expands each query
+    /// token into every indexed term within a bounded Levenshtein edit distance and
+    /// treats the token as matched if *any* of those derivations sits at the expected
+    /// phrase offset. The edit-distance budget grows with token length: short tokens
+    /// (<=4 chars) must match exactly, medium tokens (5-8 chars) tolerate one edit,
+    /// and longer tokens tolerate two.
+    pub fn search_fuzzy(&self, query: &str) -> Vec<usize> {
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let tokens = Self::get_tokens(query);
+        let tokens = self.order_tokens(&tokens);
+        if tokens.is_empty() {
+            return vec![];
+        }
+
+        let mut derivation_cache: HashMap<String, Vec<String>> = HashMap::new();
+
+        let first_derivations = derivation_cache
+            .entry(tokens[0].clone())
+            .or_insert_with(|| self.fuzzy_derivations(&tokens[0]))
+            .clone();
+        let mut possibles: HashMap<usize, Vec<usize>> = HashMap::new();
+        for derivation in &first_derivations {
+            if let Some(docs) = self.index.get(derivation) {
+                for (&doc_id, positions) in docs {
+                    possibles.entry(doc_id).or_insert_with(Vec::new).extend(positions.iter().copied());
+                }
+            }
+        }
+        if possibles.is_empty() {
+            return vec![];
+        }
+
+        for (i, token) in tokens.iter().enumerate() {
+            let derivations = derivation_cache
+                .entry(token.clone())
+                .or_insert_with(|| self.fuzzy_derivations(token))
+                .clone();
+
+            let mut current_token_positions: HashMap<usize, Vec<usize>> = HashMap::new();
+            for derivation in &derivations {
+                if let Some(docs) = self.index.get(derivation) {
+                    for (&doc_id, positions) in docs {
+                        current_token_positions.entry(doc_id).or_insert_with(Vec::new).extend(positions.iter().copied());
+                    }
+                }
+            }
+            if current_token_positions.is_empty() {
+                return vec![];
+            }
+            for positions in current_token_positions.values_mut() {
+                positions.sort_unstable();
+            }
+
+            let mut new_possibles = HashMap::new();
+            for (&candidate_doc_id, candidate_phrase_starts) in possibles.iter() {
+                if let Some(positions) = current_token_positions.get(&candidate_doc_id) {
+                    let mut new_starts = vec![];
+                    for &candidate_phrase_start in candidate_phrase_starts {
+                        if positions.binary_search(&(candidate_phrase_start + i)).is_ok() {
+                            new_starts.push(candidate_phrase_start);
+                        }
+                    }
+                    if !new_starts.is_empty() {
+                        new_possibles.insert(candidate_doc_id, new_starts);
+                    }
+                }
+            }
+            possibles = new_possibles;
+            if possibles.is_empty() {
+                return vec![];
+            }
+        }
+
+        let mut results: Vec<usize> = possibles.into_keys().collect();
+        results.sort();
+        results
+    }
+
+    /// Typo-tolerant search with an explicit, caller-chosen edit-distance budget
+    /// (capped at 2 regardless of what's passed, since larger budgets make the
+    /// per-term Levenshtein walk too permissive to be useful). Unlike `search_fuzzy`,
+    /// this treats the query as a bag of words rather than a phrase: a document
+    /// matches if *any* query token has a dictionary derivation within `max_edits`
+    /// that occurs anywhere in the document. Results are ranked by the best (lowest)
+    /// edit distance found for that document across all query tokens, ties broken by
+    /// ascending doc id.
+    pub fn search_fuzzy_ranked(&self, query: &str, max_edits: usize) -> Vec<(usize, usize)> {
+        if query.is_empty() {
+            return vec![];
+        }
+        let max_edits = max_edits.min(2);
+
+        let tokens = Self::get_tokens(query);
+        if tokens.is_empty() {
+            return vec![];
+        }
+
+        let mut best_distance: HashMap<usize, usize> = HashMap::new();
+        for token in &tokens {
+            for (term, distance) in self.fuzzy_derivations_within(token, max_edits) {
+                if let Some(docs) = self.index.get(&term) {
+                    for &doc_id in docs.keys() {
+                        best_distance.entry(doc_id)
+                            .and_modify(|existing| *existing = (*existing).min(distance))
+                            .or_insert(distance);
+                    }
+                }
+            }
+        }
+
+        let mut results: Vec<(usize, usize)> = best_distance.into_iter().collect();
+        results.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
+        results
+    }
+
+    /// Typo-tolerant search for a single term: walks the Levenshtein automaton
+    /// (`fuzzy_derivations_within`) over the indexed term dictionary to find every
+    /// term within `max_edits` of `term`, then unions their posting lists.
+    /// `max_edits` is capped at 2, same as `search_fuzzy_ranked`, for the same reason.
+    pub fn search_fuzzy_term(&self, term: &str, max_edits: usize) -> Vec<usize> {
+        if term.is_empty() {
+            return vec![];
+        }
+        let max_edits = max_edits.min(2);
+
+        let token = match Self::get_tokens(term).into_iter().next() {
+            Some(token) => token,
+            None => return vec![],
+        };
+
+        let mut doc_ids: HashSet<usize> = HashSet::new();
+        for (derivation, _distance) in self.fuzzy_derivations_within(&token, max_edits) {
+            if let Some(docs) = self.index.get(&derivation) {
+                doc_ids.extend(docs.keys().copied());
+            }
+        }
+
+        let mut results: Vec<usize> = doc_ids.into_iter().collect();
+        results.sort_unstable();
+        results
+    }
+
+    /// Like `search`, but each query token is also matched by any token sequence
+    /// registered as its synonym via `add_synonyms` (including multi-word sequences,
+    /// e.g. a query for "nyc" also matching a document's "new york"). A document
+    /// matches if, for every query token in turn, either the token itself or one of
+    /// its synonym sequences occurs immediately after the previous match ended.
+    pub fn search_with_synonyms(&self, query: &str) -> Vec<usize> {
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let tokens = Self::get_tokens(query);
+        let tokens = self.order_tokens(&tokens);
+        if tokens.is_empty() {
+            return vec![];
+        }
+
+        // Group the query tokens into steps: a run of consecutive query tokens that
+        // spells out a registered multi-word synonym (e.g. "new york") collapses into
+        // a single step so it can be matched against shorter synonyms (e.g. "nyc")
+        // too, not just longer ones.
+        let steps = self.build_synonym_steps(&tokens);
+
+        // Per document, the positions where the phrase-so-far could have just ended.
+        let mut frontiers: HashMap<usize, HashSet<usize>> = HashMap::new();
+
+        for (i, derivations) in steps.iter().enumerate() {
+            let mut new_frontiers: HashMap<usize, HashSet<usize>> = HashMap::new();
+            if i == 0 {
+                for derivation in derivations {
+                    for (doc_id, ends) in self.phrase_occurrences(derivation) {
+                        new_frontiers.entry(doc_id).or_default().extend(ends);
+                    }
+                }
+            } else {
+                for (&doc_id, ends) in frontiers.iter() {
+                    for &end in ends {
+                        for derivation in derivations {
+                            if self.phrase_at(derivation, doc_id, end) {
+                                new_frontiers.entry(doc_id).or_default().insert(end + derivation.len());
+                            }
+                        }
+                    }
+                }
+            }
+
+            if new_frontiers.is_empty() {
+                return vec![];
+            }
+            frontiers = new_frontiers;
+        }
+
+        let mut results: Vec<usize> = frontiers.into_keys().collect();
+        results.sort();
+        results
+    }
+
+    /// Groups query tokens into synonym-matching steps: greedily consumes the
+    /// longest run of tokens starting at each position that spells out some
+    /// registered synonym's multi-word form, so a query for "new york" can match a
+    /// document's "nyc" and not just the reverse. Falls back to a single-token step
+    /// when no multi-word synonym starts there.
+    fn build_synonym_steps(&self, tokens: &[String]) -> Vec<Vec<Vec<String>>> {
+        let max_span = self.synonyms.values().flatten().map(Vec::len).max().unwrap_or(1);
+
+        let mut steps = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            let max_len = max_span.min(tokens.len() - i);
+            let multi_word_match = (2..=max_len).rev().find_map(|len| {
+                let candidate = &tokens[i..i + len];
+                self.synonyms.iter()
+                    .find(|(_, alternatives)| alternatives.iter().any(|alt| alt.as_slice() == candidate))
+                    .map(|(key, _)| (len, self.synonym_derivations(key)))
+            });
+
+            match multi_word_match {
+                Some((len, derivations)) => {
+                    steps.push(derivations);
+                    i += len;
+                },
+                None => {
+                    steps.push(self.synonym_derivations(&tokens[i]));
+                    i += 1;
+                },
+            }
+        }
+        steps
+    }
+
+    /// Every token sequence that can stand in for `token`: itself, plus any
+    /// sequences registered as its synonyms.
+    fn synonym_derivations(&self, token: &str) -> Vec<Vec<String>> {
+        let mut derivations = vec![vec![token.to_string()]];
+        if let Some(alternatives) = self.synonyms.get(token) {
+            derivations.extend(alternatives.iter().cloned());
+        }
+        derivations
+    }
+
+    /// Every position in every document where `tokens` occurs as a consecutive run,
+    /// mapped to the position immediately following the run (so callers can chain
+    /// further matches onto it).
+    fn phrase_occurrences(&self, tokens: &[String]) -> HashMap<usize, Vec<usize>> {
+        let first_postings = match self.index.get(&tokens[0]) {
+            Some(postings) => postings,
+            None => return HashMap::new(),
+        };
+
+        let mut occurrences: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (&doc_id, positions) in first_postings {
+            for &start in positions {
+                if self.phrase_at(tokens, doc_id, start) {
+                    occurrences.entry(doc_id).or_default().push(start + tokens.len());
+                }
+            }
+        }
+        occurrences
+    }
+
+    /// Whether `tokens` occurs as a consecutive run in `doc_id` starting exactly at
+    /// `start`.
+    fn phrase_at(&self, tokens: &[String], doc_id: usize, start: usize) -> bool {
+        tokens.iter().enumerate().all(|(i, token)| {
+            self.index.get(token)
+                .and_then(|postings| postings.get(&doc_id))
+                .map_or(false, |positions| positions.binary_search(&(start + i)).is_ok())
+        })
+    }
+
+    /// Every position in `doc_id` where `tokens` occurs as a consecutive run,
+    /// starting from that token's own posting list rather than a candidate set
+    /// already known to contain the whole phrase.
+    fn phrase_starts(&self, tokens: &[String], doc_id: usize) -> Vec<usize> {
+        let first_positions = match self.index.get(&tokens[0]).and_then(|postings| postings.get(&doc_id)) {
+            Some(positions) => positions,
+            None => return vec![],
+        };
+        first_positions.iter().copied().filter(|&start| self.phrase_at(tokens, doc_id, start)).collect()
+    }
+
+    /// Returns every indexed term within `token`'s length-based edit-distance
+    /// budget (see `max_edits_for_token`), discarding the distances themselves.
+    fn fuzzy_derivations(&self, token: &str) -> Vec<String> {
+        self.fuzzy_derivations_within(token, Self::max_edits_for_token(token))
+            .into_iter()
+            .map(|(term, _distance)| term)
+            .collect()
+    }
+
+    /// Returns every indexed term within `max_edits` of `token`, paired with its
+    /// exact edit distance, found by walking the sorted term dictionary with a
+    /// Levenshtein DFA (a DP row per prefix) and skipping whole runs of terms that
+    /// share a prefix already known to be dead.
+    fn fuzzy_derivations_within(&self, token: &str, max_edits: usize) -> Vec<(String, usize)> {
+        let query_chars: Vec<char> = token.chars().collect();
+
+        let mut sorted_terms: Vec<&String> = self.index.keys().collect();
+        sorted_terms.sort();
+
+        let mut matches = vec![];
+        let mut dead_prefix: Option<String> = None;
+
+        for term in sorted_terms {
+            if let Some(prefix) = &dead_prefix {
+                if term.starts_with(prefix.as_str()) {
+                    continue;
+                }
+                dead_prefix = None;
+            }
+
+            let mut row: Vec<usize> = (0..=query_chars.len()).collect();
+            let mut consumed = String::new();
+            let mut alive = true;
+            for ch in term.chars() {
+                row = Self::next_levenshtein_row(&row, &query_chars, ch);
+                consumed.push(ch);
+                if row.iter().min().copied().unwrap_or(usize::MAX) > max_edits {
+                    alive = false;
+                    dead_prefix = Some(consumed);
+                    break;
+                }
+            }
+
+            let distance = row.last().copied().unwrap_or(usize::MAX);
+            if alive && distance <= max_edits {
+                matches.push((term.clone(), distance));
+            }
+        }
+
+        matches
+    }
+
+    /// Edit-distance budget for a query token: exact match for short tokens, then
+    /// widening tolerance as the token gets longer and typos become more likely.
+    fn max_edits_for_token(token: &str) -> usize {
+        match token.chars().count() {
+            0..=4 => 0,
+            5..=8 => 1,
+            _ => 2,
+        }
+    }
+
+    /// Computes the next row of the Levenshtein DP table when the automaton consumes
+    /// one more character (`next_char`) of a candidate term.
+    fn next_levenshtein_row(prev_row: &[usize], query_chars: &[char], next_char: char) -> Vec<usize> {
+        let mut row = Vec::with_capacity(prev_row.len());
+        row.push(prev_row[0] + 1);
+        for (j, &query_char) in query_chars.iter().enumerate() {
+            let substitution_cost = if query_char == next_char { 0 } else { 1 };
+            let value = (prev_row[j + 1] + 1)
+                .min(row[j] + 1)
+                .min(prev_row[j] + substitution_cost);
+            row.push(value);
+        }
+        row
+    }
+
+    /// Intersects the document-ID sets of every token's posting list via a leapfrog:
+    /// each token's doc IDs are decoded from its roaring bitmap into a sorted vec,
+    /// the rarest token (smallest doc set) drives the scan, and the others are only
+    /// ever sought into through `DocIdCursor`'s skip checkpoints, so no intermediate
+    /// intersection is built just to be thrown away. Returns `None` if any token is
+    /// entirely unindexed.
+    fn intersect_doc_ids(&self, tokens: &[String]) -> Option<Vec<usize>> {
+        let mut term_doc_ids: Vec<Vec<usize>> = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            let bitmap = self.doc_bitmaps.get(token)?;
+            term_doc_ids.push(bitmap.to_sorted_vec());
+        }
+
+        let mut order: Vec<usize> = (0..term_doc_ids.len()).collect();
+        order.sort_by_key(|&i| term_doc_ids[i].len());
+
+        let driver_idx = order[0];
+        let mut others: Vec<DocIdCursor> = order[1..].iter()
+            .map(|&i| DocIdCursor::new(&term_doc_ids[i]))
+            .collect();
+
+        let mut driver = DocIdCursor::new(&term_doc_ids[driver_idx]);
+        let mut result = Vec::new();
+        'driver: while let Some(candidate) = driver.advance() {
+            for cursor in others.iter_mut() {
+                match cursor.seek(candidate) {
+                    Some(found) if found == candidate => continue,
+                    _ => continue 'driver,
+                }
+            }
+            result.push(candidate);
+        }
+
+        Some(result)
+    }
+
+    /// Same rarest-term-drives-the-scan leapfrog as `intersect_doc_ids`, but sought
+    /// via `LinearDocIdCursor` instead of `DocIdCursor`, so it never benefits from
+    /// skip checkpoints. Exists only so `search_linear` can serve as a baseline for
+    /// measuring what the checkpoints in `intersect_doc_ids` actually buy.
+    fn intersect_doc_ids_linear(&self, tokens: &[String]) -> Option<Vec<usize>> {
+        let mut term_doc_ids: Vec<Vec<usize>> = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            let bitmap = self.doc_bitmaps.get(token)?;
+            term_doc_ids.push(bitmap.to_sorted_vec());
+        }
+
+        let mut order: Vec<usize> = (0..term_doc_ids.len()).collect();
+        order.sort_by_key(|&i| term_doc_ids[i].len());
+
+        let driver_idx = order[0];
+        let mut others: Vec<LinearDocIdCursor> = order[1..].iter()
+            .map(|&i| LinearDocIdCursor::new(&term_doc_ids[i]))
+            .collect();
+
+        let mut driver = LinearDocIdCursor::new(&term_doc_ids[driver_idx]);
+        let mut result = Vec::new();
+        'driver: while let Some(candidate) = driver.advance() {
+            for cursor in others.iter_mut() {
+                match cursor.seek(candidate) {
+                    Some(found) if found == candidate => continue,
+                    _ => continue 'driver,
+                }
+            }
+            result.push(candidate);
+        }
+
+        Some(result)
+    }
+
+    /// Bag-of-words AND search (every query token must be present somewhere in the
+    /// document, with no positional constraint) driven by `intersect_doc_ids_linear`
+    /// rather than the skip-accelerated `intersect_doc_ids`. Exists as the baseline
+    /// `benchmark_index` times against `search` to quantify the skip checkpoints'
+    /// effect on intersection latency.
+    pub fn search_linear(&self, query: &str) -> Vec<usize> {
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let tokens = Self::get_tokens(query);
+        if tokens.is_empty() {
+            return vec![];
+        }
+
+        self.intersect_doc_ids_linear(&tokens).unwrap_or_default()
+    }
+
+    pub(crate) fn get_tokens(content: &str) -> Vec<String> {
+        Self::get_tokens_with_offsets(content).into_iter().map(|(token, _, _)| token).collect()
+    }
+
+    /// Like `get_tokens`, but also returns each token's `(start, end)` character span
+    /// in `content` (the whitespace-delimited word it came from, before punctuation
+    /// stripping), so a token position can be mapped back to a slice of the original
+    /// text.
+    fn get_tokens_with_offsets(content: &str) -> Vec<(String, usize, usize)> {
+        let mut result = Vec::new();
+        let mut word = String::new();
+        let mut start = 0usize;
+
+        for (i, ch) in content.chars().enumerate() {
+            if ch.is_whitespace() {
+                if !word.is_empty() {
+                    Self::push_token_with_offset(&mut result, &word, start, i);
+                    word.clear();
+                }
+            } else {
+                if word.is_empty() {
+                    start = i;
+                }
+                word.push(ch);
+            }
+        }
+        if !word.is_empty() {
+            Self::push_token_with_offset(&mut result, &word, start, content.chars().count());
+        }
+
+        result
+    }
+
+    fn push_token_with_offset(result: &mut Vec<(String, usize, usize)>, word: &str, start: usize, end: usize) {
+        let token: String = word.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+        if !token.is_empty() {
+            result.push((token, start, end));
+        }
+    }
+
+    fn order_tokens(&self, tokens: &[String]) -> Vec<String> {
+        match self.ordering {
+            TokenOrdering::TokenOrder => tokens.clone(),
+            TokenOrdering::AscendingFrequencyOrder => {
+                let mut token_freq_pairs: Vec<(&String, &usize)> = tokens.iter()
+                    .map(|t| (t, self.term_frequencies.get(t).unwrap_or(&0)))
+                    .collect();
+                
+                token_freq_pairs.sort_by_key(|&(_, freq)| freq);
+                token_freq_pairs.into_iter().map(|(token, _)| token.clone()).collect()
+            },
+        }
+    }
+
+    pub fn get_random_terms(&self, n: usize) -> HashMap<String, usize> {
+        let mut random_terms = HashMap::new();
+    
+        if self.term_frequencies.is_empty() || n == 0 {
+            return random_terms;
+        }
+    
+        let mut rng = thread_rng();
+        let terms: Vec<&String> = self.term_frequencies.keys().collect();
+        let weights: Vec<&usize> = self.term_frequencies.values().collect();
+    
+        let dist = WeightedIndex::new(weights).unwrap();
+    
+        while random_terms.len() < n && random_terms.len() < self.term_frequencies.len() {
+            let term = terms[dist.sample(&mut rng)].clone();
+            *random_terms.entry(term.to_string()).or_insert(0) = self.term_frequencies[&term];
+        }
+
+        random_terms
+    }
+
+    pub fn approximate_term_list_size_in_bytes(&self) -> usize {
+        // Average English word is length 4.
+        let term_list_size = std::mem::size_of_val(&self.index) + &self.index.len() * (mem::size_of::<String>()+4);
+        let term_frequency_list_size = std::mem::size_of_val(&self.term_frequencies) + &self.term_frequencies.len() * (mem::size_of::<String>()+mem::size_of::<usize>());
+        return term_list_size + term_frequency_list_size;
+    }
+
+    pub fn approximate_posting_list_sizes_in_bytes(&self) -> Vec<usize> {
+        let mut sizes = vec![];
+        for (term, posting_list) in &self.index {
+            sizes.push(self.posting_list_size_in_bytes(term, posting_list));
+        }
+        sizes.sort();
+        sizes
+    }
+
+    pub fn approximate_posting_list_sizes_in_bytes_by_term(&self) -> HashMap<String, usize> {
+        let mut sizes = HashMap::new();
+        for (term, posting_list) in &self.index {
+            sizes.insert(term.clone(), self.posting_list_size_in_bytes(term, posting_list));
+        }
+        sizes
+    }
+
+    /// A term's posting-list size: the roaring bitmap's real compressed size for the
+    /// document set, plus one `usize` per stored position.
+    fn posting_list_size_in_bytes(&self, term: &str, posting_list: &HashMap<usize, Vec<usize>>) -> usize {
+        let positions_size: usize = posting_list.values()
+            .map(|positions| positions.len() * mem::size_of::<usize>())
+            .sum();
+        let doc_set_size = self.doc_bitmaps.get(term)
+            .map_or(0, RoaringBitmap::approximate_size_in_bytes);
+        positions_size + doc_set_size
+    }
+
+    /// Writes the index to `path` in the compact binary format: a fixed header
+    /// (magic bytes, format version, document/term counts, and section offsets),
+    /// a metadata section (token ordering, synonyms, per-document token offsets),
+    /// a term dictionary (each term's posting-list offset within the posting
+    /// region), and finally the posting-list region itself. Each posting list is a
+    /// 4-byte length prefix (so a reader can skip it without decoding) followed by
+    /// gap-and-varint-encoded doc IDs and, per document, gap-and-varint-encoded
+    /// token positions.
+    ///
+    /// This always re-encodes every posting list from `self.index`, the same as
+    /// the full-JSON rewrite it replaced — the per-term length prefix makes a
+    /// reader's skip O(1), but there's no incremental writer yet that rewrites
+    /// only the posting lists a single `index_document` call touched and leaves
+    /// the rest of the file alone. `main.rs`'s `index` subcommand is therefore
+    /// still O(index size) per document, just with a smaller constant.
+    pub fn write_binary(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut metadata_buf = Vec::new();
+        metadata_buf.push(match self.ordering {
+            TokenOrdering::TokenOrder => 0u8,
+            TokenOrdering::AscendingFrequencyOrder => 1u8,
+        });
+
+        binary::write_varint(&mut metadata_buf, self.synonyms.len() as u64)?;
+        for (token, alternatives) in &self.synonyms {
+            binary::write_string(&mut metadata_buf, token)?;
+            binary::write_varint(&mut metadata_buf, alternatives.len() as u64)?;
+            for alternative in alternatives {
+                binary::write_varint(&mut metadata_buf, alternative.len() as u64)?;
+                for word in alternative {
+                    binary::write_string(&mut metadata_buf, word)?;
+                }
+            }
+        }
+
+        binary::write_varint(&mut metadata_buf, self.doc_token_offsets.len() as u64)?;
+        for (&doc_id, offsets) in &self.doc_token_offsets {
+            binary::write_varint(&mut metadata_buf, doc_id as u64)?;
+            binary::write_varint(&mut metadata_buf, offsets.len() as u64)?;
+            for &(start, end) in offsets {
+                binary::write_varint(&mut metadata_buf, start as u64)?;
+                binary::write_varint(&mut metadata_buf, end as u64)?;
+            }
+        }
+
+        let mut terms: Vec<&String> = self.index.keys().collect();
+        terms.sort();
+
+        let mut term_dict_buf = Vec::new();
+        let mut postings_buf = Vec::new();
+        for &term in &terms {
+            let posting_list = &self.index[term];
+            let mut doc_ids: Vec<usize> = posting_list.keys().copied().collect();
+            doc_ids.sort();
+
+            let mut posting_buf = Vec::new();
+            binary::write_varint(&mut posting_buf, doc_ids.len() as u64)?;
+            let mut prev_doc_id = 0usize;
+            for doc_id in doc_ids {
+                binary::write_varint(&mut posting_buf, (doc_id - prev_doc_id) as u64)?;
+                prev_doc_id = doc_id;
+
+                let positions = &posting_list[&doc_id];
+                binary::write_varint(&mut posting_buf, positions.len() as u64)?;
+                let mut prev_position = 0usize;
+                for &position in positions {
+                    binary::write_varint(&mut posting_buf, (position - prev_position) as u64)?;
+                    prev_position = position;
+                }
+            }
+
+            binary::write_string(&mut term_dict_buf, term)?;
+            term_dict_buf.extend_from_slice(&(postings_buf.len() as u64).to_le_bytes());
+
+            postings_buf.extend_from_slice(&(posting_buf.len() as u32).to_le_bytes());
+            postings_buf.extend_from_slice(&posting_buf);
+        }
+
+        const HEADER_LEN: u64 = 4 + 4 + 4 + 4 + 8 + 8;
+        let term_dict_offset = HEADER_LEN + metadata_buf.len() as u64;
+        let posting_region_offset = term_dict_offset + term_dict_buf.len() as u64;
+
+        let file = fs::File::create(path)?;
+        let mut writer = io::BufWriter::new(file);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.doc_lengths.len() as u32).to_le_bytes())?;
+        writer.write_all(&(terms.len() as u32).to_le_bytes())?;
+        writer.write_all(&term_dict_offset.to_le_bytes())?;
+        writer.write_all(&posting_region_offset.to_le_bytes())?;
+        writer.write_all(&metadata_buf)?;
+        writer.write_all(&term_dict_buf)?;
+        writer.write_all(&postings_buf)?;
+
+        Ok(())
+    }
+
+    /// Reads an index written by `write_binary`, validating the magic bytes and
+    /// format version before reconstructing every field: the term/posting data
+    /// decodes directly off the wire, while `doc_bitmaps`, `term_frequencies`, and
+    /// `doc_lengths` are rebuilt from it (they are pure functions of the postings,
+    /// so persisting them separately would just be redundant bytes on disk).
+    pub fn read_binary(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let bytes = fs::read(path)?;
+        let mut cursor = bytes.as_slice();
+
+        let mut magic = [0u8; 4];
+        cursor.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(format!("not a positional inverted index file (bad magic bytes {:?})", magic).into());
+        }
+
+        let version = read_u32(&mut cursor)?;
+        if version != VERSION {
+            return Err(format!("unsupported index format version {} (expected {})", version, VERSION).into());
+        }
+
+        let _doc_count = read_u32(&mut cursor)?;
+        let term_count = read_u32(&mut cursor)? as usize;
+        let _term_dict_offset = read_u64(&mut cursor)?;
+        let posting_region_offset = read_u64(&mut cursor)? as usize;
+
+        let ordering_tag = {
+            let mut tag = [0u8; 1];
+            cursor.read_exact(&mut tag)?;
+            tag[0]
+        };
+        let ordering = match ordering_tag {
+            1 => TokenOrdering::AscendingFrequencyOrder,
+            _ => TokenOrdering::TokenOrder,
+        };
+
+        let synonym_count = binary::read_varint(&mut cursor)?;
+        let mut synonyms = HashMap::new();
+        for _ in 0..synonym_count {
+            let token = binary::read_string(&mut cursor)?;
+            let alternative_count = binary::read_varint(&mut cursor)?;
+            let mut alternatives = Vec::with_capacity(alternative_count as usize);
+            for _ in 0..alternative_count {
+                let word_count = binary::read_varint(&mut cursor)?;
+                let mut words = Vec::with_capacity(word_count as usize);
+                for _ in 0..word_count {
+                    words.push(binary::read_string(&mut cursor)?);
+                }
+                alternatives.push(words);
+            }
+            synonyms.insert(token, alternatives);
+        }
+
+        let doc_offset_count = binary::read_varint(&mut cursor)?;
+        let mut doc_token_offsets = HashMap::new();
+        for _ in 0..doc_offset_count {
+            let doc_id = binary::read_varint(&mut cursor)? as usize;
+            let span_count = binary::read_varint(&mut cursor)?;
+            let mut spans = Vec::with_capacity(span_count as usize);
+            for _ in 0..span_count {
+                let start = binary::read_varint(&mut cursor)? as usize;
+                let end = binary::read_varint(&mut cursor)? as usize;
+                spans.push((start, end));
+            }
+            doc_token_offsets.insert(doc_id, spans);
+        }
+
+        let mut term_offsets = Vec::with_capacity(term_count);
+        for _ in 0..term_count {
+            let term = binary::read_string(&mut cursor)?;
+            let offset = read_u64(&mut cursor)?;
+            term_offsets.push((term, offset));
+        }
+
+        let mut index: HashMap<String, HashMap<usize, Vec<usize>>> = HashMap::new();
+        let mut doc_bitmaps: HashMap<String, RoaringBitmap> = HashMap::new();
+        let mut term_frequencies: HashMap<String, usize> = HashMap::new();
+        let mut doc_lengths: HashMap<usize, usize> = HashMap::new();
+
+        for (term, offset) in term_offsets {
+            let mut posting_cursor = &bytes[posting_region_offset + offset as usize..];
+            let byte_len = read_u32(&mut posting_cursor)? as usize;
+            let mut posting_cursor = &posting_cursor[..byte_len];
+
+            let doc_count = binary::read_varint(&mut posting_cursor)?;
+            let mut postings: HashMap<usize, Vec<usize>> = HashMap::new();
+            let mut doc_id = 0usize;
+            for _ in 0..doc_count {
+                doc_id += binary::read_varint(&mut posting_cursor)? as usize;
+                doc_bitmaps.entry(term.clone()).or_insert_with(RoaringBitmap::new).insert(doc_id);
+
+                let position_count = binary::read_varint(&mut posting_cursor)?;
+                let mut positions = Vec::with_capacity(position_count as usize);
+                let mut position = 0usize;
+                for _ in 0..position_count {
+                    position += binary::read_varint(&mut posting_cursor)? as usize;
+                    positions.push(position);
+                }
+
+                *term_frequencies.entry(term.clone()).or_insert(0) += positions.len();
+                *doc_lengths.entry(doc_id).or_insert(0) += positions.len();
+                postings.insert(doc_id, positions);
+            }
+            index.insert(term, postings);
+        }
+
+        Ok(PositionalInvertedIndex {
+            index,
+            doc_bitmaps,
+            term_frequencies,
+            ordering,
+            doc_lengths,
+            synonyms,
+            doc_token_offsets,
+            analyzer: default_analyzer(),
+        })
+    }
+}
+
+fn read_u32(cursor: &mut &[u8]) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    cursor.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(cursor: &mut &[u8]) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    cursor.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let index = PositionalInvertedIndex::new();
+        assert!(index.index.is_empty());
+    }
+
+    #[test]
+    fn test_index_single_document() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        assert_eq!(index.index["hello"][&1], vec![0]);
+        assert_eq!(index.index["world"][&1], vec![1]);
+    }
+
+    #[test]
+    fn test_index_multiple_documents() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        index.index_document(2, "world of rust");
+        assert_eq!(index.index["world"][&1], vec![1]);
+        assert_eq!(index.index["world"][&2], vec![0]);
+        assert_eq!(index.index["rust"][&2], vec![2]);
+    }
+
+    #[test]
+    fn test_search_nonpresent_token() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        index.index_document(2, "world of rust");
+        let results = index.search("foo");
+        assert_eq!(results.len(), 0);
+    }
+
+    #[test]
+    fn test_search_single_token() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        index.index_document(2, "world of rust");
+        let results = index.search("world");
+        assert_eq!(results, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_search_multi_token_single_result() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        index.index_document(2, "world of rust");
+        let results = index.search("hello world");
+        assert_eq!(results, vec![1]);
+    }
+
+    #[test]
+    fn test_search_multi_token_multi_result_simple() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world hello rust");
+        index.index_document(2, "world of hell rust hello");
+        index.index_document(3, "hello rust");
+        let results1 = index.search("hello rust");
+        assert_eq!(results1, vec![1, 3]);
+        let results2 = index.search("hell");
+        assert_eq!(results2, vec![2]);
+    }
+
+    #[test]
+    fn test_search_multi_token_multi_result_complex() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "This is a longer string with more tokens than any other test case");
+        index.index_document(2, "This is another long string with many more tokens so many tokens Look how many");
+        index.index_document(3, "And finally we have a third document with a few tokens but still many tokens relatively");
+        let results1 = index.search("many tokens");
+        assert_eq!(results1, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_empty_index_term_list_size() {
+        let index = PositionalInvertedIndex::new();
+        assert!(index.approximate_term_list_size_in_bytes() > 0);
+        assert!(index.approximate_term_list_size_in_bytes() < 100);
+    }
+
+    #[test]
+    fn test_increasing_size_increases_term_list_size() {
+        let mut index = PositionalInvertedIndex::new();
+        let initial_size = index.approximate_term_list_size_in_bytes();
+
+        index.index_document(1, "test document one");
+        let first_size = index.approximate_term_list_size_in_bytes();
+        assert!(first_size > initial_size);
+
+        index.index_document(2, "another test document");
+        let second_size = index.approximate_term_list_size_in_bytes();
+        assert!(second_size > first_size);
+    }
+
+    #[test]
+    fn test_term_list_size_is_reasonable_for_large_index() {
+        let mut index = PositionalInvertedIndex::new();
+        for i in 1..=1000 {
+            index.index_document(i, "some repetitive test document content");
+        }
+
+        let size = index.approximate_term_list_size_in_bytes();
+        assert!(size < 1000000);
+    }
+
+    #[test]
+    fn test_empty_index_posting_list_sizes() {
+        let index = PositionalInvertedIndex::new();
+        assert!(index.approximate_posting_list_sizes_in_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_single_term_posting_list_size() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "test");
+        let sizes = index.approximate_posting_list_sizes_in_bytes();
+        assert_eq!(sizes.len(), 1);
+        assert!(sizes[0] > 0);
+    }
+
+    #[test]
+    fn test_multiple_terms_correct_number_of_posting_list_sizes() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "test document");
+        index.index_document(2, "another test document");
+        let sizes = index.approximate_posting_list_sizes_in_bytes();
+        assert_eq!(sizes.len(), 3);
+    }
+
+    #[test]
+    fn test_multiple_documents_multiple_terms_correct_number_of_posting_list_sizes() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "test document");
+        index.index_document(2, "another test document");
+
+        let sizes = index.approximate_posting_list_sizes_in_bytes();
+        assert_eq!(sizes.len(), 3);
+    }
+
+    #[test]
+    fn test_posting_list_sizes_sorted() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "test document");
+        index.index_document(2, "another test document");
+
+        let sizes = index.approximate_posting_list_sizes_in_bytes();
+        assert!(sizes[0] <= sizes[1]);
+        assert!(sizes[1] <= sizes[2]);
+    }
+
+    #[test]
+    fn test_increasing_size_increases_posting_list_sizes() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "a document");
+        index.index_document(2, "a bit longer document");
+
+        let initial_sizes = index.approximate_posting_list_sizes_in_bytes();
+        assert!(initial_sizes[0] <= initial_sizes[1]);
+
+        index.index_document(3, "a bit longer document");
+        index.index_document(4, "a bit longer document");
+
+        let final_sizes = index.approximate_posting_list_sizes_in_bytes();
+
+        for i in 0..3 {
+            assert!(initial_sizes[i] < final_sizes[i]);
+        }
+    }
+
+    #[test]
+    fn test_get_random_terms_count() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "apple orange banana");
+        index.index_document(2, "apple banana");
+
+        let random_terms = index.get_random_terms(2);
+        assert_eq!(random_terms.len(), 2);
+    }
+
+    #[test]
+    fn test_get_random_terms_distribution() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "apple apple apple orange banana");
+        index.index_document(2, "banana apple");
+
+        let mut apple_count = 0;
+        let total_count = 1000;
+        for _ in 0..total_count {
+            let random_terms = index.get_random_terms(1);
+            if random_terms.contains_key(&"apple".to_string()) {
+                apple_count += 1;
+            }
+        }
+
+        assert!(apple_count > total_count / 3);
+    }
+
+    #[test]
+    fn test_get_random_terms_correct_weights() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "apple apple apple orange banana");
+        index.index_document(2, "banana apple");
+
+        let random_terms = index.get_random_terms(10);
+
+        assert!(random_terms["apple"] == 4);
+        assert!(random_terms["orange"] == 1);
+        assert!(random_terms["banana"] == 2);
+    }
+
+    #[test]
+    fn test_get_random_terms_with_empty_index() {
+        let index = PositionalInvertedIndex::new();
+        let random_terms = index.get_random_terms(2);
+        assert!(random_terms.is_empty());
+    }
+
+    #[test]
+    fn test_get_random_terms_more_than_unique_terms() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "apple orange");
+
+        let random_terms = index.get_random_terms(5);
+        assert_eq!(random_terms.len(), 2);
+    }
+
+    #[test]
+    fn test_posting_list_sizes_by_term_empty_index() {
+        let index = PositionalInvertedIndex::new();
+        assert!(index.approximate_posting_list_sizes_in_bytes_by_term().is_empty());
+    }
+
+    #[test]
+    fn test_posting_list_sizes_by_term_single_term_index() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "term1");
+        let sizes = index.approximate_posting_list_sizes_in_bytes_by_term();
+        assert!(sizes.get("term1").unwrap() > &(0 as usize));
+    }
+
+    #[test]
+    fn test_posting_list_sizes_by_term_multiple_terms() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "apple orange");
+        index.index_document(1, "apple orange banana");
+        let sizes = index.approximate_posting_list_sizes_in_bytes_by_term();
+        assert_eq!(sizes.get("apple").unwrap(), sizes.get("orange").unwrap());
+        assert!(sizes.get("apple").unwrap() > sizes.get("banana").unwrap());
+    }
+
+    #[test]
+    fn test_get_tokens_with_regular_text() {
+        let content = "Hello world";
+        let tokens = PositionalInvertedIndex::get_tokens(content);
+        assert_eq!(tokens, vec!["hello", "world"]);
+    }
+
+    #[test]
+    fn test_get_tokens_with_special_characters() {
+        let content = "Hello, world!";
+        let tokens = PositionalInvertedIndex::get_tokens(content);
+        assert_eq!(tokens, vec!["hello", "world"]);
+    }
+
+    #[test]
+    fn test_get_tokens_with_numbers() {
+        let content = "2024 is the year";
+        let tokens = PositionalInvertedIndex::get_tokens(content);
+        assert_eq!(tokens, vec!["2024", "is", "the", "year"]);
+    }
+
+    #[test]
+    fn test_get_tokens_with_mixed_characters() {
+        let content = "Email@example.com is an,,, e-mail address!";
+        let tokens = PositionalInvertedIndex::get_tokens(content);
+        assert_eq!(tokens, vec!["emailexamplecom", "is", "an", "email", "address"]);
+    }
+
+    #[test]
+    fn test_get_tokens_with_empty_string() {
+        let content = "";
+        let tokens = PositionalInvertedIndex::get_tokens(content);
+        assert!(tokens.is_empty());
+    }
+
+    #[test]
+    fn test_get_tokens_with_whitespace_only() {
+        let content = "   ";
+        let tokens = PositionalInvertedIndex::get_tokens(content);
+        assert!(tokens.is_empty());
+    }
+
+    #[test]
+    fn test_order_tokens_token_order() {
+        let index = PositionalInvertedIndex::with_ordering(TokenOrdering::TokenOrder);
+        let tokens = vec!["apple".to_string(), "banana".to_string(), "apple".to_string()];
+        let ordered_tokens = index.order_tokens(&tokens);
+        assert_eq!(ordered_tokens, tokens);
+    }
+
+    #[test]
+    fn test_search_fuzzy_exact_match() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        let results = index.search_fuzzy("hello world");
+        assert_eq!(results, vec![1]);
+    }
+
+    #[test]
+    fn test_search_fuzzy_single_edit() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        let results = index.search_fuzzy("hallo world");
+        assert_eq!(results, vec![1]);
+    }
+
+    #[test]
+    fn test_search_fuzzy_short_token_requires_exact_match() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "cat sat on a mat");
+        let results = index.search_fuzzy("cot");
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_search_fuzzy_too_many_edits_no_match() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        let results = index.search_fuzzy("xyzzy world");
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_search_fuzzy_empty_query() {
+        let index = PositionalInvertedIndex::new();
+        assert!(index.search_fuzzy("").is_empty());
+    }
+
+    #[test]
+    fn test_max_edits_for_token_thresholds() {
+        assert_eq!(PositionalInvertedIndex::max_edits_for_token("abcd"), 0);
+        assert_eq!(PositionalInvertedIndex::max_edits_for_token("abcdefgh"), 1);
+        assert_eq!(PositionalInvertedIndex::max_edits_for_token("abcdefghijk"), 2);
+    }
+
+    #[test]
+    fn test_fuzzy_derivations_finds_nearby_terms() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "testing texting banana");
+        let derivations = index.fuzzy_derivations("testing");
+        assert!(derivations.contains(&"testing".to_string()));
+        assert!(derivations.contains(&"texting".to_string()));
+        assert!(!derivations.contains(&"banana".to_string()));
+    }
+
+    #[test]
+    fn test_search_fuzzy_ranked_sorts_by_ascending_edit_distance() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "testing");
+        index.index_document(2, "texting");
+        let results = index.search_fuzzy_ranked("testing", 1);
+        assert_eq!(results, vec![(1, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn test_search_fuzzy_ranked_unions_across_query_tokens() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello");
+        index.index_document(2, "world");
+        let results = index.search_fuzzy_ranked("hello world", 0);
+        let doc_ids: Vec<usize> = results.iter().map(|&(doc_id, _)| doc_id).collect();
+        assert_eq!(doc_ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_search_fuzzy_ranked_caps_max_edits_at_two() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "testing");
+        index.index_document(2, "tempting");
+        let capped = index.search_fuzzy_ranked("testing", 10);
+        let uncapped = index.search_fuzzy_ranked("testing", 2);
+        assert_eq!(capped, uncapped);
+    }
+
+    #[test]
+    fn test_search_fuzzy_ranked_no_match_beyond_budget() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        let results = index.search_fuzzy_ranked("xyzzy", 1);
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_search_fuzzy_ranked_empty_query() {
+        let index = PositionalInvertedIndex::new();
+        assert!(index.search_fuzzy_ranked("", 1).is_empty());
+    }
+
+    #[test]
+    fn test_search_fuzzy_term_single_edit() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "testing");
+        index.index_document(2, "unrelated");
+        let results = index.search_fuzzy_term("tasting", 1);
+        assert_eq!(results, vec![1]);
+    }
+
+    #[test]
+    fn test_search_fuzzy_term_exact_match() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        let results = index.search_fuzzy_term("hello", 0);
+        assert_eq!(results, vec![1]);
+    }
+
+    #[test]
+    fn test_search_fuzzy_term_no_match_beyond_budget() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        let results = index.search_fuzzy_term("xyzzy", 1);
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_search_fuzzy_term_caps_max_edits_at_two() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "testing");
+        let capped = index.search_fuzzy_term("testing", 10);
+        let uncapped = index.search_fuzzy_term("testing", 2);
+        assert_eq!(capped, uncapped);
+    }
+
+    #[test]
+    fn test_search_fuzzy_term_empty_term() {
+        let index = PositionalInvertedIndex::new();
+        assert!(index.search_fuzzy_term("", 1).is_empty());
+    }
+
+    #[test]
+    fn test_search_boolean_implicit_and() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        index.index_document(2, "hello rust");
+        let results = index.search_boolean("hello world");
+        assert_eq!(results, vec![1]);
+    }
+
+    #[test]
+    fn test_search_boolean_or() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        index.index_document(2, "rust programming");
+        index.index_document(3, "unrelated content");
+        let results = index.search_boolean("world OR rust");
+        assert_eq!(results, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_search_boolean_not() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        index.index_document(2, "hello rust");
+        let results = index.search_boolean("hello -rust");
+        assert_eq!(results, vec![1]);
+    }
+
+    #[test]
+    fn test_search_boolean_quoted_phrase() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        index.index_document(2, "world hello");
+        let results = index.search_boolean("\"hello world\"");
+        assert_eq!(results, vec![1]);
+    }
+
+    #[test]
+    fn test_search_boolean_no_match() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        let results = index.search_boolean("missing");
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_search_boolean_parenthesized_group() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        index.index_document(2, "rust programming");
+        index.index_document(3, "unrelated content");
+        let results = index.search_boolean("(hello OR rust) AND world");
+        assert_eq!(results, vec![1]);
+    }
+
+    #[test]
+    fn test_index_document_populates_doc_bitmaps() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        index.index_document(2, "world of rust");
+        assert!(index.doc_bitmaps["hello"].contains(1));
+        assert!(index.doc_bitmaps["world"].contains(1));
+        assert!(index.doc_bitmaps["world"].contains(2));
+        assert!(!index.doc_bitmaps["rust"].contains(1));
+    }
+
+    #[test]
+    fn test_merge_combines_disjoint_shards() {
+        let mut first = PositionalInvertedIndex::new();
+        first.index_document(0, "hello world");
+        let mut second = PositionalInvertedIndex::new();
+        second.index_document(1, "hello rust");
+
+        first.merge(second);
+
+        assert_eq!(first.search("hello"), vec![0, 1]);
+        assert_eq!(first.search("world"), vec![0]);
+        assert_eq!(first.search("rust"), vec![1]);
+        assert_eq!(*first.term_frequencies.get("hello").unwrap(), 2);
+        assert!(first.doc_bitmaps["hello"].contains(0));
+        assert!(first.doc_bitmaps["hello"].contains(1));
+    }
+
+    #[test]
+    fn test_build_parallel_from_chunks_matches_sequential_build() {
+        let chunks = vec![
+            vec![(0usize, "hello world".to_string())],
+            vec![(1usize, "hello rust".to_string()), (2usize, "rust programming".to_string())],
+        ];
+        let parallel = PositionalInvertedIndex::build_parallel_from_chunks(chunks);
+
+        let mut sequential = PositionalInvertedIndex::new();
+        sequential.index_document(0, "hello world");
+        sequential.index_document(1, "hello rust");
+        sequential.index_document(2, "rust programming");
+
+        assert_eq!(parallel.search("hello"), sequential.search("hello"));
+        assert_eq!(parallel.search("rust"), sequential.search("rust"));
+        assert_eq!(parallel.search("programming"), vec![2]);
+    }
+
+    #[test]
+    fn test_posting_list_size_includes_bitmap_size() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "test");
+        let posting_list = &index.index["test"];
+        let size = index.posting_list_size_in_bytes("test", posting_list);
+        let bitmap_size = index.doc_bitmaps["test"].approximate_size_in_bytes();
+        assert!(size >= bitmap_size);
+    }
+
+    #[test]
+    fn test_doc_id_cursor_advance() {
+        let doc_ids = vec![1, 4, 7, 9];
+        let mut cursor = DocIdCursor::new(&doc_ids);
+        assert_eq!(cursor.advance(), Some(1));
+        assert_eq!(cursor.advance(), Some(4));
+        assert_eq!(cursor.advance(), Some(7));
+        assert_eq!(cursor.advance(), Some(9));
+        assert_eq!(cursor.advance(), None);
+    }
+
+    #[test]
+    fn test_doc_id_cursor_seek_lands_on_target() {
+        let doc_ids = vec![1, 4, 7, 9];
+        let mut cursor = DocIdCursor::new(&doc_ids);
+        assert_eq!(cursor.seek(7), Some(7));
+    }
+
+    #[test]
+    fn test_doc_id_cursor_seek_past_gap_finds_next_largest() {
+        let doc_ids = vec![1, 4, 7, 20, 40, 80];
+        let mut cursor = DocIdCursor::new(&doc_ids);
+        assert_eq!(cursor.seek(8), Some(20));
+    }
+
+    #[test]
+    fn test_doc_id_cursor_seek_beyond_end_is_none() {
+        let doc_ids = vec![1, 4, 7];
+        let mut cursor = DocIdCursor::new(&doc_ids);
+        assert_eq!(cursor.seek(100), None);
+    }
+
+    #[test]
+    fn test_doc_id_cursor_seek_spans_multiple_skip_checkpoints() {
+        let doc_ids: Vec<usize> = (0..1000).map(|i| i * 2).collect();
+        let mut cursor = DocIdCursor::new(&doc_ids);
+        assert_eq!(cursor.seek(999), Some(1000));
+        assert_eq!(cursor.seek(1500), Some(1500));
+        assert_eq!(cursor.advance(), Some(1502));
+    }
+
+    #[test]
+    fn test_intersect_doc_ids_common_terms_only() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "rust is great");
+        index.index_document(2, "rust is slow");
+        index.index_document(3, "python is great");
+        let tokens = vec!["rust".to_string(), "is".to_string()];
+        let mut docs = index.intersect_doc_ids(&tokens).unwrap();
+        docs.sort();
+        assert_eq!(docs, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_intersect_doc_ids_missing_term_is_none() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "rust is great");
+        let tokens = vec!["rust".to_string(), "missing".to_string()];
+        assert!(index.intersect_doc_ids(&tokens).is_none());
+    }
+
+    #[test]
+    fn test_linear_doc_id_cursor_seek_finds_next_largest() {
+        let doc_ids = vec![2, 5, 9, 14, 20];
+        let mut cursor = LinearDocIdCursor::new(&doc_ids);
+        assert_eq!(cursor.seek(6), Some(9));
+        assert_eq!(cursor.seek(20), Some(20));
+        assert_eq!(cursor.seek(21), None);
     }
 
-    pub fn approximate_posting_list_sizes_in_bytes_by_term(&self) -> HashMap<String, usize> {
-        let mut sizes = HashMap::new();
-        for (term, posting_list) in &self.index {
-            let mut size = 0;
-            for (_doc_id, positions) in posting_list {
-                // Add 1 to account for the doc ID.
-                size += (positions.len() + 1) * mem::size_of::<usize>();
-            }
-            sizes.insert(term.clone(), size);
+    #[test]
+    fn test_intersect_doc_ids_linear_matches_skip_accelerated() {
+        let mut index = PositionalInvertedIndex::new();
+        for i in 0..500 {
+            index.index_document(i, if i % 3 == 0 { "rust is great" } else { "python is great" });
         }
-        sizes
+        let tokens = vec!["rust".to_string(), "is".to_string()];
+        let mut linear = index.intersect_doc_ids_linear(&tokens).unwrap();
+        let mut skip = index.intersect_doc_ids(&tokens).unwrap();
+        linear.sort();
+        skip.sort();
+        assert_eq!(linear, skip);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_search_linear_matches_search_for_and_semantics() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "rust is great");
+        index.index_document(2, "rust is slow");
+        index.index_document(3, "python is great");
+        assert_eq!(index.search_linear("rust is"), vec![1, 2]);
+    }
 
     #[test]
-    fn test_new() {
+    fn test_search_linear_empty_query() {
         let index = PositionalInvertedIndex::new();
-        assert!(index.index.is_empty());
+        assert!(index.search_linear("").is_empty());
     }
 
     #[test]
-    fn test_index_single_document() {
+    fn test_search_ranked_scores_more_frequent_term_higher() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "hello world");
-        assert_eq!(index.index["hello"][&1], vec![0]);
-        assert_eq!(index.index["world"][&1], vec![1]);
+        index.index_document(1, "rust rust rust systems programming");
+        index.index_document(2, "rust is fun");
+        let results = index.search_ranked("rust", false);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, 1);
+        assert!(results[0].1 > results[1].1);
     }
 
     #[test]
-    fn test_index_multiple_documents() {
+    fn test_search_ranked_bag_of_words_matches_any_term() {
         let mut index = PositionalInvertedIndex::new();
         index.index_document(1, "hello world");
         index.index_document(2, "world of rust");
-        assert_eq!(index.index["world"][&1], vec![1]);
-        assert_eq!(index.index["world"][&2], vec![0]);
-        assert_eq!(index.index["rust"][&2], vec![2]);
+        let results = index.search_ranked("hello rust", false);
+        let doc_ids: Vec<usize> = results.iter().map(|&(id, _)| id).collect();
+        assert_eq!(doc_ids.len(), 2);
+        assert!(doc_ids.contains(&1));
+        assert!(doc_ids.contains(&2));
     }
 
     #[test]
-    fn test_search_nonpresent_token() {
+    fn test_search_ranked_require_phrase_restricts_candidates() {
         let mut index = PositionalInvertedIndex::new();
         index.index_document(1, "hello world");
-        index.index_document(2, "world of rust");
-        let results = index.search("foo");
-        assert_eq!(results.len(), 0);
+        index.index_document(2, "world hello");
+        let results = index.search_ranked("hello world", true);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, 1);
     }
 
     #[test]
-    fn test_search_single_token() {
+    fn test_search_ranked_empty_query() {
+        let index = PositionalInvertedIndex::new();
+        assert!(index.search_ranked("", false).is_empty());
+    }
+
+    #[test]
+    fn test_search_ranked_top_k_truncates_results() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "rust rust rust systems programming");
+        index.index_document(2, "rust is fun");
+        index.index_document(3, "rust programming language");
+        let results = index.search_ranked_top_k("rust", false, 2);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, 1);
+    }
+
+    #[test]
+    fn test_search_ranked_top_k_larger_than_results_returns_all() {
         let mut index = PositionalInvertedIndex::new();
         index.index_document(1, "hello world");
-        index.index_document(2, "world of rust");
-        let results = index.search("world");
-        assert_eq!(results, vec![1, 2]);
+        let results = index.search_ranked_top_k("hello", false, 10);
+        assert_eq!(results.len(), 1);
     }
 
     #[test]
-    fn test_search_multi_token_single_result() {
+    fn test_search_with_matches_returns_char_spans_of_matched_tokens() {
         let mut index = PositionalInvertedIndex::new();
         index.index_document(1, "hello world");
-        index.index_document(2, "world of rust");
-        let results = index.search("hello world");
-        assert_eq!(results, vec![1]);
+        let results = index.search_with_matches("hello world");
+        assert_eq!(results, vec![(1, vec![(0, 5), (6, 11)])]);
     }
 
     #[test]
-    fn test_search_multi_token_multi_result_simple() {
+    fn test_search_with_matches_span_covers_original_word_not_stripped_token() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "hello world hello rust");
-        index.index_document(2, "world of hell rust hello");
-        index.index_document(3, "hello rust");
-        let results1 = index.search("hello rust");
-        assert_eq!(results1, vec![1, 3]);
-        let results2 = index.search("hell");
-        assert_eq!(results2, vec![2]);
+        index.index_document(1, "hello, world!");
+        let results = index.search_with_matches("hello");
+        assert_eq!(results, vec![(1, vec![(0, 6)])]);
     }
 
     #[test]
-    fn test_search_multi_token_multi_result_complex() {
+    fn test_search_with_matches_merges_spans_from_multiple_occurrences() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "This is a longer string with more tokens than any other test case");
-        index.index_document(2, "This is another long string with many more tokens so many tokens Look how many");
-        index.index_document(3, "And finally we have a third document with a few tokens but still many tokens relatively");
-        let results1 = index.search("many tokens");
-        assert_eq!(results1, vec![2, 3]);
+        index.index_document(1, "hello world hello world");
+        let results = index.search_with_matches("hello");
+        assert_eq!(results, vec![(1, vec![(0, 5), (12, 17)])]);
     }
 
     #[test]
-    fn test_empty_index_term_list_size() {
+    fn test_search_with_matches_no_match_returns_empty() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello world");
+        assert!(index.search_with_matches("goodbye").is_empty());
+    }
+
+    #[test]
+    fn test_search_with_matches_empty_query() {
         let index = PositionalInvertedIndex::new();
-        assert!(index.approximate_term_list_size_in_bytes() > 0);
-        assert!(index.approximate_term_list_size_in_bytes() < 100);
+        assert!(index.search_with_matches("").is_empty());
     }
 
     #[test]
-    fn test_increasing_size_increases_term_list_size() {
+    fn test_search_with_slop_exact_adjacent_is_zero_cost() {
         let mut index = PositionalInvertedIndex::new();
-        let initial_size = index.approximate_term_list_size_in_bytes();
-
-        index.index_document(1, "test document one");
-        let first_size = index.approximate_term_list_size_in_bytes();
-        assert!(first_size > initial_size);
+        index.index_document(1, "hello world");
+        let results = index.search_with_slop("hello world", 2);
+        assert_eq!(results, vec![(1, 0)]);
+    }
 
-        index.index_document(2, "another test document");
-        let second_size = index.approximate_term_list_size_in_bytes();
-        assert!(second_size > first_size);
+    #[test]
+    fn test_search_with_slop_allows_gap_within_budget() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello there big world");
+        let results = index.search_with_slop("hello world", 2);
+        assert_eq!(results, vec![(1, 2)]);
     }
 
     #[test]
-    fn test_term_list_size_is_reasonable_for_large_index() {
+    fn test_search_with_slop_rejects_gap_over_budget() {
         let mut index = PositionalInvertedIndex::new();
-        for i in 1..=1000 {
-            index.index_document(i, "some repetitive test document content");
-        }
+        index.index_document(1, "hello there big world");
+        let results = index.search_with_slop("hello world", 0);
+        assert!(results.is_empty());
+    }
 
-        let size = index.approximate_term_list_size_in_bytes();
-        assert!(size < 1000000);
+    #[test]
+    fn test_search_with_slop_ranks_tighter_matches_first() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "hello there big world");
+        index.index_document(2, "hello world");
+        let results = index.search_with_slop("hello world", 2);
+        assert_eq!(results, vec![(2, 0), (1, 2)]);
     }
 
     #[test]
-    fn test_empty_index_posting_list_sizes() {
+    fn test_search_with_slop_empty_query() {
         let index = PositionalInvertedIndex::new();
-        assert!(index.approximate_posting_list_sizes_in_bytes().is_empty());
+        assert!(index.search_with_slop("", 2).is_empty());
     }
 
     #[test]
-    fn test_single_term_posting_list_size() {
+    fn test_search_phrase_matches_adjacent_terms() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "test");
-        let sizes = index.approximate_posting_list_sizes_in_bytes();
-        assert_eq!(sizes.len(), 1);
-        assert!(sizes[0] > 0);
+        index.index_document(1, "hello world");
+        index.index_document(2, "world hello");
+        assert_eq!(index.search_phrase(&["hello world"]), vec![1]);
     }
 
     #[test]
-    fn test_multiple_terms_correct_number_of_posting_list_sizes() {
+    fn test_search_phrase_accepts_multiple_term_operands() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "test document");
-        index.index_document(2, "another test document");
-        let sizes = index.approximate_posting_list_sizes_in_bytes();
-        assert_eq!(sizes.len(), 3);
+        index.index_document(1, "hello world rust");
+        assert_eq!(index.search_phrase(&["hello", "world rust"]), vec![1]);
     }
 
     #[test]
-    fn test_multiple_documents_multiple_terms_correct_number_of_posting_list_sizes() {
+    fn test_search_proximity_matches_within_budget() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "test document");
-        index.index_document(2, "another test document");
-
-        let sizes = index.approximate_posting_list_sizes_in_bytes();
-        assert_eq!(sizes.len(), 3);
+        index.index_document(1, "hello there big world");
+        let results = index.search_proximity(&["hello", "world"], 3);
+        assert_eq!(results, vec![1]);
     }
 
     #[test]
-    fn test_posting_list_sizes_sorted() {
+    fn test_search_proximity_rejects_over_budget() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "test document");
-        index.index_document(2, "another test document");
+        index.index_document(1, "hello there big world");
+        let results = index.search_proximity(&["hello", "world"], 2);
+        assert!(results.is_empty());
+    }
 
-        let sizes = index.approximate_posting_list_sizes_in_bytes();
-        assert!(sizes[0] <= sizes[1]);
-        assert!(sizes[1] <= sizes[2]);
+    #[test]
+    fn test_search_proximity_is_order_independent() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "world hello");
+        let results = index.search_proximity(&["hello", "world"], 1);
+        assert_eq!(results, vec![1]);
     }
 
     #[test]
-    fn test_increasing_size_increases_posting_list_sizes() {
+    fn test_search_proximity_no_match_when_term_missing() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "a document");
-        index.index_document(2, "a bit longer document");
+        index.index_document(1, "hello there");
+        assert!(index.search_proximity(&["hello", "world"], 5).is_empty());
+    }
 
-        let initial_sizes = index.approximate_posting_list_sizes_in_bytes();
-        assert!(initial_sizes[0] <= initial_sizes[1]);
+    #[test]
+    fn test_search_with_synonyms_single_word_synonym() {
+        let mut index = PositionalInvertedIndex::new();
+        index.add_synonyms(&["couch", "sofa"]);
+        index.index_document(1, "a comfortable sofa");
+        let results = index.search_with_synonyms("couch");
+        assert_eq!(results, vec![1]);
+    }
 
-        index.index_document(3, "a bit longer document");
-        index.index_document(4, "a bit longer document");
+    #[test]
+    fn test_search_with_synonyms_multi_word_synonym_matches_single_word_query() {
+        let mut index = PositionalInvertedIndex::new();
+        index.add_synonyms(&["nyc", "new york"]);
+        index.index_document(1, "i love new york");
+        let results = index.search_with_synonyms("nyc");
+        assert_eq!(results, vec![1]);
+    }
 
-        let final_sizes = index.approximate_posting_list_sizes_in_bytes();
+    #[test]
+    fn test_search_with_synonyms_single_word_synonym_matches_multi_word_query() {
+        let mut index = PositionalInvertedIndex::new();
+        index.add_synonyms(&["nyc", "new york"]);
+        index.index_document(1, "i love nyc");
+        let results = index.search_with_synonyms("new york");
+        assert_eq!(results, vec![1]);
+    }
 
-        for i in 0..3 {
-            assert!(initial_sizes[i] < final_sizes[i]);
-        }
+    #[test]
+    fn test_search_with_synonyms_without_registered_synonym_falls_back_to_exact_match() {
+        let mut index = PositionalInvertedIndex::new();
+        index.add_synonyms(&["couch", "sofa"]);
+        index.index_document(1, "a wooden table");
+        assert!(index.search_with_synonyms("couch").is_empty());
     }
 
     #[test]
-    fn test_get_random_terms_count() {
+    fn test_search_with_synonyms_preserves_surrounding_phrase_adjacency() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "apple orange banana");
-        index.index_document(2, "apple banana");
+        index.add_synonyms(&["nyc", "new york"]);
+        index.index_document(1, "i love new york city");
+        index.index_document(2, "new york is not loved here");
+        let results = index.search_with_synonyms("love nyc");
+        assert_eq!(results, vec![1]);
+    }
 
-        let random_terms = index.get_random_terms(2);
-        assert_eq!(random_terms.len(), 2);
+    #[test]
+    fn test_search_with_synonyms_empty_query() {
+        let index = PositionalInvertedIndex::new();
+        assert!(index.search_with_synonyms("").is_empty());
     }
 
     #[test]
-    fn test_get_random_terms_distribution() {
-        let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "apple apple apple orange banana");
-        index.index_document(2, "banana apple");
+    fn test_order_tokens_ascending_frequency_order() {
+        let mut index = PositionalInvertedIndex::with_ordering(TokenOrdering::AscendingFrequencyOrder);
 
-        let mut apple_count = 0;
-        let total_count = 1000;
-        for _ in 0..total_count {
-            let random_terms = index.get_random_terms(1);
-            if random_terms.contains_key(&"apple".to_string()) {
-                apple_count += 1;
-            }
-        }
+        // Index some documents to create frequencies
+        index.index_document(1, "apple apple apple apple apple cherry");
+        index.index_document(2, "banana cherry cherry");
 
-        assert!(apple_count > total_count / 3);
+        let tokens = vec!["apple".to_string(), "cherry".to_string(), "banana".to_string()];
+        let ordered_tokens = index.order_tokens(&tokens);
+        assert_eq!(ordered_tokens, vec!["banana".to_string(), "cherry".to_string(), "apple".to_string()]);
     }
 
     #[test]
-    fn test_get_random_terms_correct_weights() {
+    fn test_write_binary_then_read_binary_roundtrips_search_results() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "apple apple apple orange banana");
-        index.index_document(2, "banana apple");
+        index.index_document(1, "hello world");
+        index.index_document(2, "goodbye world");
+        let path = std::env::temp_dir().join("piix_test_roundtrip_search.bin");
 
-        let random_terms = index.get_random_terms(10);
+        index.write_binary(path.to_str().unwrap()).unwrap();
+        let reloaded = PositionalInvertedIndex::read_binary(path.to_str().unwrap()).unwrap();
 
-        assert!(random_terms["apple"] == 4);
-        assert!(random_terms["orange"] == 1);
-        assert!(random_terms["banana"] == 2);
+        assert_eq!(reloaded.search("hello world"), vec![1]);
+        assert_eq!(reloaded.search("world"), vec![1, 2]);
+        std::fs::remove_file(path).unwrap();
     }
 
     #[test]
-    fn test_get_random_terms_with_empty_index() {
-        let index = PositionalInvertedIndex::new();
-        let random_terms = index.get_random_terms(2);
-        assert!(random_terms.is_empty());
+    fn test_write_binary_then_read_binary_preserves_ordering() {
+        let mut index = PositionalInvertedIndex::with_ordering(TokenOrdering::AscendingFrequencyOrder);
+        index.index_document(1, "apple apple apple cherry");
+        let path = std::env::temp_dir().join("piix_test_roundtrip_ordering.bin");
+
+        index.write_binary(path.to_str().unwrap()).unwrap();
+        let reloaded = PositionalInvertedIndex::read_binary(path.to_str().unwrap()).unwrap();
+
+        let tokens = vec!["apple".to_string(), "cherry".to_string()];
+        assert_eq!(reloaded.order_tokens(&tokens), vec!["cherry".to_string(), "apple".to_string()]);
+        std::fs::remove_file(path).unwrap();
     }
 
     #[test]
-    fn test_get_random_terms_more_than_unique_terms() {
+    fn test_write_binary_then_read_binary_preserves_synonyms() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "apple orange");
+        index.add_synonyms(&["nyc", "new york"]);
+        index.index_document(1, "i love new york");
+        let path = std::env::temp_dir().join("piix_test_roundtrip_synonyms.bin");
 
-        let random_terms = index.get_random_terms(5);
-        assert_eq!(random_terms.len(), 2);
-    }
+        index.write_binary(path.to_str().unwrap()).unwrap();
+        let reloaded = PositionalInvertedIndex::read_binary(path.to_str().unwrap()).unwrap();
 
-    #[test]
-    fn test_posting_list_sizes_by_term_empty_index() {
-        let index = PositionalInvertedIndex::new();
-        assert!(index.approximate_posting_list_sizes_in_bytes_by_term().is_empty());
+        assert_eq!(reloaded.search_with_synonyms("nyc"), vec![1]);
+        std::fs::remove_file(path).unwrap();
     }
 
     #[test]
-    fn test_posting_list_sizes_by_term_single_term_index() {
+    fn test_write_binary_then_read_binary_preserves_match_offsets() {
         let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "term1");
-        let sizes = index.approximate_posting_list_sizes_in_bytes_by_term();
-        assert!(sizes.get("term1").unwrap() > &(0 as usize));
+        index.index_document(1, "hello world");
+        let path = std::env::temp_dir().join("piix_test_roundtrip_offsets.bin");
+
+        index.write_binary(path.to_str().unwrap()).unwrap();
+        let reloaded = PositionalInvertedIndex::read_binary(path.to_str().unwrap()).unwrap();
+
+        assert_eq!(reloaded.search_with_matches("hello world"), vec![(1, vec![(0, 5), (6, 11)])]);
+        std::fs::remove_file(path).unwrap();
     }
 
     #[test]
-    fn test_posting_list_sizes_by_term_multiple_terms() {
-        let mut index = PositionalInvertedIndex::new();
-        index.index_document(1, "apple orange");
-        index.index_document(1, "apple orange banana");
-        let sizes = index.approximate_posting_list_sizes_in_bytes_by_term();
-        assert_eq!(sizes.get("apple").unwrap(), sizes.get("orange").unwrap());
-        assert!(sizes.get("apple").unwrap() > sizes.get("banana").unwrap());
+    fn test_read_binary_rejects_bad_magic_bytes() {
+        let path = std::env::temp_dir().join("piix_test_bad_magic.bin");
+        std::fs::write(&path, b"NOPE garbage content").unwrap();
+
+        assert!(PositionalInvertedIndex::read_binary(path.to_str().unwrap()).is_err());
+        std::fs::remove_file(path).unwrap();
     }
 
     #[test]
-    fn test_get_tokens_with_regular_text() {
-        let content = "Hello world";
-        let tokens = PositionalInvertedIndex::get_tokens(content);
-        assert_eq!(tokens, vec!["hello", "world"]);
+    fn test_read_binary_rejects_unsupported_version() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&999u32.to_le_bytes());
+        let path = std::env::temp_dir().join("piix_test_bad_version.bin");
+        std::fs::write(&path, &bytes).unwrap();
+
+        assert!(PositionalInvertedIndex::read_binary(path.to_str().unwrap()).is_err());
+        std::fs::remove_file(path).unwrap();
     }
 
     #[test]
-    fn test_get_tokens_with_special_characters() {
-        let content = "Hello, world!";
-        let tokens = PositionalInvertedIndex::get_tokens(content);
-        assert_eq!(tokens, vec!["hello", "world"]);
+    fn test_standard_analyzer_is_identity() {
+        let analyzer = StandardAnalyzer;
+        assert_eq!(analyzer.process_token("running"), Some("running".to_string()));
     }
 
     #[test]
-    fn test_get_tokens_with_numbers() {
-        let content = "2024 is the year";
-        let tokens = PositionalInvertedIndex::get_tokens(content);
-        assert_eq!(tokens, vec!["2024", "is", "the", "year"]);
+    fn test_stemming_analyzer_collapses_inflected_forms() {
+        let analyzer = StemmingAnalyzer::new(false);
+        assert_eq!(analyzer.process_token("running"), analyzer.process_token("runs"));
     }
 
     #[test]
-    fn test_get_tokens_with_mixed_characters() {
-        let content = "Email@example.com is an,,, e-mail address!";
-        let tokens = PositionalInvertedIndex::get_tokens(content);
-        assert_eq!(tokens, vec!["emailexamplecom", "is", "an", "email", "address"]);
+    fn test_stemming_analyzer_removes_stop_words_when_enabled() {
+        let analyzer = StemmingAnalyzer::new(true);
+        assert_eq!(analyzer.process_token("the"), None);
+        assert!(analyzer.process_token("rust").is_some());
     }
 
     #[test]
-    fn test_get_tokens_with_empty_string() {
-        let content = "";
-        let tokens = PositionalInvertedIndex::get_tokens(content);
-        assert!(tokens.is_empty());
+    fn test_stemming_analyzer_keeps_stop_words_when_disabled() {
+        let analyzer = StemmingAnalyzer::new(false);
+        assert_eq!(analyzer.process_token("the"), Some("the".to_string()));
     }
 
     #[test]
-    fn test_get_tokens_with_whitespace_only() {
-        let content = "   ";
-        let tokens = PositionalInvertedIndex::get_tokens(content);
-        assert!(tokens.is_empty());
+    fn test_with_analyzer_search_matches_stemmed_query() {
+        let mut index = PositionalInvertedIndex::with_analyzer(Box::new(StemmingAnalyzer::new(false)));
+        index.index_document(1, "she runs quickly");
+        assert_eq!(index.search("running"), vec![1]);
     }
 
     #[test]
-    fn test_order_tokens_token_order() {
-        let index = PositionalInvertedIndex::with_ordering(TokenOrdering::TokenOrder);
-        let tokens = vec!["apple".to_string(), "banana".to_string(), "apple".to_string()];
-        let ordered_tokens = index.order_tokens(&tokens);
-        assert_eq!(ordered_tokens, tokens);
+    fn test_with_analyzer_stop_word_removal_shrinks_index() {
+        let mut with_stop_words = PositionalInvertedIndex::with_analyzer(Box::new(StemmingAnalyzer::new(false)));
+        with_stop_words.index_document(1, "the quick fox");
+        assert!(with_stop_words.search("the").contains(&1));
+
+        let mut without_stop_words = PositionalInvertedIndex::with_analyzer(Box::new(StemmingAnalyzer::new(true)));
+        without_stop_words.index_document(1, "the quick fox");
+        assert!(without_stop_words.search("the").is_empty());
     }
 
     #[test]
-    fn test_order_tokens_ascending_frequency_order() {
-        let mut index = PositionalInvertedIndex::with_ordering(TokenOrdering::AscendingFrequencyOrder);
-
-        // Index some documents to create frequencies
-        index.index_document(1, "apple apple apple apple apple cherry");
-        index.index_document(2, "banana cherry cherry");
+    fn test_with_analyzer_search_boolean_respects_analyzer() {
+        let mut index = PositionalInvertedIndex::with_analyzer(Box::new(StemmingAnalyzer::new(true)));
+        index.index_document(1, "she runs quickly");
+        assert_eq!(index.search_boolean("running AND NOT the"), vec![1]);
+    }
 
-        let tokens = vec!["apple".to_string(), "cherry".to_string(), "banana".to_string()];
-        let ordered_tokens = index.order_tokens(&tokens);
-        assert_eq!(ordered_tokens, vec!["banana".to_string(), "cherry".to_string(), "apple".to_string()]);
+    #[test]
+    fn test_standard_analyzer_is_default_for_new() {
+        let mut index = PositionalInvertedIndex::new();
+        index.index_document(1, "running");
+        assert!(index.search("runs").is_empty());
     }
 }
\ No newline at end of file
@@ -1,9 +1,14 @@
 mod benchmark;
+mod binary;
+mod bulk_index;
 mod idx;
 mod plot;
+mod query;
 mod query_tokens;
+mod roaring;
 
 use benchmark::benchmark_index;
+use bulk_index::BulkFormat;
 use idx::{
     PositionalInvertedIndex,
     TokenOrdering
@@ -20,10 +25,7 @@ use clap::{
     Arg,
     SubCommand
 };
-use std::{
-    fs,
-    path::Path
-};
+use std::path::Path;
 
 fn main() {
     let matches = App::new("Positional Inverted Index CLI")
@@ -48,7 +50,48 @@ fn main() {
                 .required(true))
             .arg(Arg::with_name("QUERY")
                 .help("The query string to search for")
-                .required(true)))
+                .required(true))
+            .arg(Arg::with_name("fuzzy")
+                .long("fuzzy")
+                .help("Tolerate up to N edits per query token (capped at 2), ranked by edit distance")
+                .takes_value(true)))
+        .subcommand(SubCommand::with_name("bulk_index")
+            .about("Indexes every document in a CSV, JSON, or NDJSON file")
+            .arg(Arg::with_name("INDEX")
+                .help("Sets the path to the index file")
+                .required(true))
+            .arg(Arg::with_name("INPUT")
+                .help("The path to the CSV/JSON/NDJSON file to index")
+                .required(true))
+            .arg(Arg::with_name("format")
+                .long("format")
+                .help("The input file's format")
+                .takes_value(true)
+                .possible_values(&["csv", "json", "ndjson"])
+                .required(true))
+            .arg(Arg::with_name("id_field")
+                .long("id-field")
+                .help("The field to use as the document ID; auto-assigned sequentially if omitted")
+                .takes_value(true))
+            .arg(Arg::with_name("text_fields")
+                .long("text-fields")
+                .help("The field(s) to concatenate as document content")
+                .takes_value(true)
+                .multiple(true)
+                .required(true))
+            .arg(Arg::with_name("jobs")
+                .long("jobs")
+                .help("Number of shards to index in parallel (default 1, sequential)")
+                .takes_value(true)))
+        .subcommand(SubCommand::with_name("build_corpus")
+            .about("Builds a fresh index from one or more blank-line-delimited paragraph files in parallel with rayon, one shard per file")
+            .arg(Arg::with_name("INDEX")
+                .help("Sets the path to write the built index file")
+                .required(true))
+            .arg(Arg::with_name("Filenames")
+                .help("The paragraph files to index")
+                .required(true)
+                .multiple(true)))
         .subcommand(SubCommand::with_name("term_list_size")
             .about("Prints the approximate size of the term list in bytes")
             .arg(Arg::with_name("INDEX")
@@ -73,7 +116,7 @@ fn main() {
             .arg(Arg::with_name("query_token_distribution")
                 .help("The distribution of query tokens")
                 .takes_value(true)
-                .possible_values(&["fixed", "uniform", "from_document"])
+                .possible_values(&["fixed", "uniform", "from_document", "boolean"])
                 .required(true))
             .arg(Arg::with_name("Token Search Ordering")
                 .help("The ordering of tokens in the query")
@@ -83,6 +126,13 @@ fn main() {
             .arg(Arg::with_name("Target Directory")
                 .help("The target directory to store benchmark results")
                 .required(true))
+            .arg(Arg::with_name("fuzzy")
+                .long("fuzzy")
+                .help("Time fuzzy term lookups (tolerating up to N edits, capped at 2) instead of exact search")
+                .takes_value(true))
+            .arg(Arg::with_name("stem")
+                .long("stem")
+                .help("Build the index with a stemming, stop-word-removing analyzer instead of the default, so index size and query latency can be compared with and without stemming"))
             .arg(Arg::with_name("Filenames")
                 .help("The filenames to index")
                 .required(true)
@@ -115,10 +165,18 @@ fn main() {
             .arg(Arg::with_name("N")
                 .help("The number of queries to plot")
                 .required(true)))
+        .subcommand(SubCommand::with_name("report")
+            .about("Generates a single self-contained HTML report bundling all benchmark charts and summary stats")
+            .arg(Arg::with_name("Target Directory")
+                .help("The target directory to read benchmark results from and write report.html to")
+                .required(true)))
         .get_matches();
 
     match matches.subcommand() {
         ("index", Some(sub_m)) => {
+            // NOTE: still a full read-modify-rewrite, same as the JSON format it
+            // replaced, just in binary now — see the doc comment on write_binary
+            // for why "only touched posting lists get rewritten" isn't true yet.
             let index_path = sub_m.value_of("INDEX").unwrap();
             let mut index = read_or_create_index(index_path);
 
@@ -126,16 +184,65 @@ fn main() {
             let content = sub_m.value_of("CONTENT").unwrap();
             index.index_document(doc_id, content);
 
-            let serialized = serde_json::to_string(&index).expect("Unable to serialize index");
-            fs::write(index_path, serialized).expect("Unable to write file");
+            index.write_binary(index_path).expect("Unable to write index file");
         },
         ("search", Some(sub_m)) => {
             let index_path = sub_m.value_of("INDEX").unwrap();
             let index = read_or_create_index(index_path);
 
             let query = sub_m.value_of("QUERY").unwrap();
-            let results = index.search(query);
-            println!("Search results: {:?}", results);
+            match sub_m.value_of("fuzzy") {
+                Some(max_edits) => {
+                    let max_edits = max_edits.parse::<usize>().expect("Invalid fuzzy edit distance");
+                    let results = index.search_fuzzy_ranked(query, max_edits);
+                    println!("Search results: {:?}", results);
+                },
+                None => {
+                    let results = match query::parse_positional_query(query) {
+                        Some(query::PositionalQuery::Phrase(phrase)) => index.search_phrase(&[phrase.as_str()]),
+                        Some(query::PositionalQuery::Proximity(left, right, k)) => {
+                            index.search_proximity(&[left.as_str(), right.as_str()], k)
+                        },
+                        None => index.search(query),
+                    };
+                    println!("Search results: {:?}", results);
+                },
+            }
+        },
+        ("bulk_index", Some(sub_m)) => {
+            let index_path = sub_m.value_of("INDEX").unwrap();
+            let mut index = read_or_create_index(index_path);
+
+            let input_path = sub_m.value_of("INPUT").unwrap();
+            let format = match sub_m.value_of("format").unwrap() {
+                "csv" => BulkFormat::Csv,
+                "json" => BulkFormat::Json,
+                "ndjson" => BulkFormat::Ndjson,
+                _ => panic!("Invalid format"),
+            };
+            let id_field = sub_m.value_of("id_field");
+            let text_fields: Vec<String> = sub_m.values_of("text_fields").unwrap().map(|s| s.to_string()).collect();
+            let jobs = sub_m.value_of("jobs").map_or(1, |n| n.parse::<usize>().expect("Invalid jobs count"));
+
+            match bulk_index::bulk_index(&mut index, input_path, format, id_field, &text_fields, jobs) {
+                Ok(count) => {
+                    index.write_binary(index_path).expect("Unable to write index file");
+                    println!("Indexed {} documents", count);
+                },
+                Err(e) => println!("Bulk index failed: {}", e),
+            }
+        },
+        ("build_corpus", Some(sub_m)) => {
+            let index_path = sub_m.value_of("INDEX").unwrap();
+            let filenames: Vec<String> = sub_m.values_of("Filenames").unwrap().map(|s| s.to_string()).collect();
+
+            match PositionalInvertedIndex::build_parallel(&filenames) {
+                Ok(index) => {
+                    index.write_binary(index_path).expect("Unable to write index file");
+                    println!("Built corpus index at {}", index_path);
+                },
+                Err(e) => println!("Corpus build failed: {}", e),
+            }
         },
         ("term_list_size", Some(sub_m)) => {
             let index_path = sub_m.value_of("INDEX").unwrap();
@@ -159,6 +266,7 @@ fn main() {
                 "fixed" => QueryTokenDistribution::Fixed,
                 "uniform" => QueryTokenDistribution::Uniform,
                 "from_document" => QueryTokenDistribution::FromDocument,
+                "boolean" => QueryTokenDistribution::Boolean,
                 _ => panic!("Invalid query token distribution"),
             };
             let token_search_ordering = match sub_m.value_of("Token Search Ordering").unwrap() {
@@ -166,7 +274,9 @@ fn main() {
                 "frequency" => TokenOrdering::AscendingFrequencyOrder,
                 _ => panic!("Invalid token search ordering"),
             };
-            match benchmark_index(filenames, query_frequency, num_queries, max_query_tokens, query_token_distribution, token_search_ordering, target_directory) {
+            let fuzzy_max_edits = sub_m.value_of("fuzzy").map(|n| n.parse::<usize>().expect("Invalid fuzzy edit distance"));
+            let use_stemming = sub_m.is_present("stem");
+            match benchmark_index(filenames, query_frequency, num_queries, max_query_tokens, query_token_distribution, token_search_ordering, target_directory, fuzzy_max_edits, use_stemming) {
                 Ok(_) => println!("Benchmarking {} completed successfully", target_directory),
                 Err(e) => println!("Benchmark failed: {}", e),
             }
@@ -203,16 +313,22 @@ fn main() {
                 Err(e) => println!("Plot failed: {}", e),
             }
         },
+        ("report", Some(sub_m)) => {
+            let target_directory = sub_m.value_of("Target Directory").unwrap();
+
+            match plot::generate_report(target_directory) {
+                Ok(_) => println!("Report written to {}/report.html", target_directory),
+                Err(e) => println!("Report generation failed: {}", e),
+            }
+        },
         _ => panic!("You must specify a subcommand: either 'index' or 'search'"),
     }
 }
 
 fn read_or_create_index(index_path: &str) -> PositionalInvertedIndex {
-    let index = if Path::new(index_path).exists() {
-        let data = fs::read_to_string(index_path).expect("Unable to read file");
-        serde_json::from_str(&data).expect("Unable to parse file")
+    if Path::new(index_path).exists() {
+        PositionalInvertedIndex::read_binary(index_path).expect("Unable to read index file")
     } else {
         PositionalInvertedIndex::new()
-    };
-    index
+    }
 }
\ No newline at end of file
@@ -1,6 +1,7 @@
 use plotters::prelude::*;
 use std::{
     error::Error,
+    fs,
     path::Path
 };
 
@@ -111,7 +112,6 @@ pub fn plot_query_latency(target_dir: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-<<<<<<< HEAD
 pub fn plot_posting_list_distribution(target_dir: &str) -> Result<(), Box<dyn Error>> {
     let input_path = Path::new(target_dir).join("posting_list_sizes.csv");
     let output_path = Path::new(target_dir).join("posting_list_sizes.png");
@@ -193,8 +193,6 @@ fn plot_line_with_std_dev(
     Ok(())
 }
 
-=======
->>>>>>> parent of 7ca681f (Posting list sizes graphing)
 fn plot_documents_to_latency_chart(
     data: Vec<(i32, u128)>, 
     output_path: &Path, 
@@ -226,3 +224,190 @@ fn plot_documents_to_latency_chart(
 
     Ok(())
 }
+
+pub fn plot_term_list_sizes(target_dir: &str) -> Result<(), Box<dyn Error>> {
+    let input_path = Path::new(target_dir).join("term_list_sizes.csv");
+    let output_path = Path::new(target_dir).join("term_list_sizes.png");
+
+    let data = read_document_count_series(&input_path)?;
+    let max_document_count = data.iter().map(|&(x, _)| x).max().unwrap_or_default();
+    let max_size = data.iter().map(|&(_, y)| y).max().unwrap_or_default();
+
+    plot_documents_to_latency_chart(
+        data,
+        &output_path,
+        max_document_count,
+        max_size,
+        "Document Count vs Term List Size (bytes)",
+        "Document Count",
+        "Term List Size (bytes)",
+    )?;
+    Ok(())
+}
+
+/// Reads the leading `(document_count, value)` columns of each row of a CSV, the
+/// shape shared by `index_latency.csv` and `term_list_sizes.csv`; any further
+/// columns (such as `index_latency.csv`'s start-of-document text) are ignored.
+fn read_document_count_series(path: &Path) -> Result<Vec<(i32, u128)>, Box<dyn Error>> {
+    let mut rdr = csv::Reader::from_path(path)?;
+    let mut data = Vec::new();
+    for result in rdr.records() {
+        let record = result?;
+        let document_count: i32 = record[0].parse()?;
+        let value: u128 = record[1].parse()?;
+        data.push((document_count, value));
+    }
+    Ok(data)
+}
+
+/// Reads `query_latency.csv`'s `(document_count, query, duration)` rows.
+fn read_query_latency_rows(target_dir: &str) -> Result<Vec<(i32, String, u128)>, Box<dyn Error>> {
+    let input_path = Path::new(target_dir).join("query_latency.csv");
+    let mut rdr = csv::Reader::from_path(input_path)?;
+    let mut rows = Vec::new();
+    for result in rdr.records() {
+        let record = result?;
+        let document_count: i32 = record[0].parse()?;
+        let query: String = record[1].parse()?;
+        let duration: u128 = record[2].parse()?;
+        rows.push((document_count, query, duration));
+    }
+    Ok(rows)
+}
+
+/// Mean/median/p95/p99 over a latency sample, computed by sorting once and
+/// indexing into it rather than maintaining a running histogram.
+fn latency_stats(values: &[u128]) -> (f64, u128, u128, u128) {
+    if values.is_empty() {
+        return (0.0, 0, 0, 0);
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let mean = sorted.iter().sum::<u128>() as f64 / sorted.len() as f64;
+    let percentile = |pct: f64| sorted[(((sorted.len() - 1) as f64) * pct).round() as usize];
+
+    (mean, percentile(0.5), percentile(0.95), percentile(0.99))
+}
+
+/// Reads a PNG from `target_dir` and returns it as a `data:` URI, so the HTML
+/// report can embed it inline instead of linking to a sibling file.
+fn embed_png_as_data_uri(target_dir: &str, filename: &str) -> Result<String, Box<dyn Error>> {
+    let bytes = fs::read(Path::new(target_dir).join(filename))?;
+    Ok(format!("data:image/png;base64,{}", base64::encode(bytes)))
+}
+
+/// Generates a single self-contained `report.html` in `target_dir`, bundling the
+/// index-latency, query-latency, posting-list-size, and term-list-size charts
+/// (as inline base64 PNGs) alongside summary latency stats and the slowest
+/// queries observed, so a benchmark run can be archived and diffed as one file.
+pub fn generate_report(target_dir: &str) -> Result<(), Box<dyn Error>> {
+    plot_index_latency(target_dir)?;
+    plot_query_latency(target_dir)?;
+    plot_posting_list_distribution(target_dir)?;
+    plot_term_list_sizes(target_dir)?;
+
+    let index_latencies = read_document_count_series(&Path::new(target_dir).join("index_latency.csv"))?;
+    let query_rows = read_query_latency_rows(target_dir)?;
+    let term_list_sizes = read_document_count_series(&Path::new(target_dir).join("term_list_sizes.csv"))?;
+
+    let total_documents = index_latencies.len();
+    let final_term_list_size_bytes = term_list_sizes.last().map(|&(_, size)| size).unwrap_or(0);
+
+    let index_durations: Vec<u128> = index_latencies.iter().map(|&(_, duration)| duration).collect();
+    let query_durations: Vec<u128> = query_rows.iter().map(|&(_, _, duration)| duration).collect();
+    let (index_mean, index_median, index_p95, index_p99) = latency_stats(&index_durations);
+    let (query_mean, query_median, query_p95, query_p99) = latency_stats(&query_durations);
+
+    let top_n = 10;
+    let mut slowest_queries = query_rows.clone();
+    slowest_queries.sort_by(|a, b| b.2.cmp(&a.2));
+    slowest_queries.truncate(top_n);
+
+    let top_queries_rows: String = slowest_queries.iter()
+        .map(|(doc_count, query, duration)| format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            doc_count, html_escape(query), duration,
+        ))
+        .collect();
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Benchmark Report</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; margin-bottom: 2em; }}
+th, td {{ border: 1px solid #ccc; padding: 0.4em 0.8em; text-align: right; }}
+th {{ background: #f0f0f0; }}
+td:nth-child(2) {{ text-align: left; }}
+img {{ max-width: 100%; margin-bottom: 2em; }}
+</style>
+</head>
+<body>
+<h1>Benchmark Report</h1>
+
+<h2>Summary</h2>
+<table>
+<tr><th>Metric</th><th>Value</th></tr>
+<tr><td>Total documents</td><td>{total_documents}</td></tr>
+<tr><td>Term list size (bytes)</td><td>{final_term_list_size_bytes}</td></tr>
+<tr><td>Index latency mean (µs)</td><td>{index_mean:.2}</td></tr>
+<tr><td>Index latency median (µs)</td><td>{index_median}</td></tr>
+<tr><td>Index latency p95 (µs)</td><td>{index_p95}</td></tr>
+<tr><td>Index latency p99 (µs)</td><td>{index_p99}</td></tr>
+<tr><td>Query latency mean (µs)</td><td>{query_mean:.2}</td></tr>
+<tr><td>Query latency median (µs)</td><td>{query_median}</td></tr>
+<tr><td>Query latency p95 (µs)</td><td>{query_p95}</td></tr>
+<tr><td>Query latency p99 (µs)</td><td>{query_p99}</td></tr>
+</table>
+
+<h2>Slowest queries</h2>
+<table>
+<tr><th>Document Count</th><th>Query</th><th>Duration (µs)</th></tr>
+{top_queries_rows}
+</table>
+
+<h2>Charts</h2>
+<h3>Index latency</h3>
+<img src="{index_latency_img}">
+<h3>Query latency</h3>
+<img src="{query_latency_img}">
+<h3>Posting list size distribution</h3>
+<img src="{posting_list_sizes_img}">
+<h3>Term list size</h3>
+<img src="{term_list_sizes_img}">
+</body>
+</html>
+"#,
+        total_documents = total_documents,
+        final_term_list_size_bytes = final_term_list_size_bytes,
+        index_mean = index_mean,
+        index_median = index_median,
+        index_p95 = index_p95,
+        index_p99 = index_p99,
+        query_mean = query_mean,
+        query_median = query_median,
+        query_p95 = query_p95,
+        query_p99 = query_p99,
+        top_queries_rows = top_queries_rows,
+        index_latency_img = embed_png_as_data_uri(target_dir, "index_latency.png")?,
+        query_latency_img = embed_png_as_data_uri(target_dir, "query_latency.png")?,
+        posting_list_sizes_img = embed_png_as_data_uri(target_dir, "posting_list_sizes.png")?,
+        term_list_sizes_img = embed_png_as_data_uri(target_dir, "term_list_sizes.png")?,
+    );
+
+    fs::write(Path::new(target_dir).join("report.html"), html)?;
+    Ok(())
+}
+
+/// Escapes the handful of characters that would otherwise break out of an HTML
+/// table cell if a query happened to contain them.
+fn html_escape(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
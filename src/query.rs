@@ -0,0 +1,388 @@
+use crate::idx::PositionalInvertedIndex;
+
+/// A boolean query tree: `Term`/`Phrase` are the leaves that produce a candidate
+/// doc-id set, and `And`/`Or`/`Not` combine those sets. `Phrase` preserves the
+/// index's positional adjacency semantics rather than treating its tokens as a
+/// bag of words.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    And(Vec<Operation>),
+    Or(Vec<Operation>),
+    Not(Box<Operation>),
+    Phrase(Vec<String>),
+    Term(String),
+}
+
+#[derive(Clone)]
+enum RawToken {
+    Word(String),
+    Quoted(String),
+    LParen,
+    RParen,
+}
+
+/// A query recognized as exploiting stored positions directly, bypassing the
+/// boolean query tree entirely: an exact quoted phrase, or a `term1 NEAR/k
+/// term2` proximity check. Returned by `parse_positional_query` so the `search`
+/// subcommand can dispatch straight to `search_phrase`/`search_proximity`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PositionalQuery {
+    Phrase(String),
+    Proximity(String, String, usize),
+}
+
+/// Recognizes the `"exact phrase"` and `term1 NEAR/k term2` query syntaxes and
+/// returns the operands to run through `search_phrase`/`search_proximity`.
+/// Returns `None` for anything else, so callers can fall back to ordinary
+/// free-text `search`.
+pub fn parse_positional_query(query: &str) -> Option<PositionalQuery> {
+    let trimmed = query.trim();
+
+    if trimmed.len() >= 2 && trimmed.starts_with('"') && trimmed.ends_with('"') {
+        return Some(PositionalQuery::Phrase(trimmed[1..trimmed.len() - 1].to_string()));
+    }
+
+    let words: Vec<&str> = trimmed.split_whitespace().collect();
+    let near_idx = words.iter().position(|word| word.to_ascii_uppercase().starts_with("NEAR/"))?;
+    let k: usize = words[near_idx][5..].parse().ok()?;
+    let left = words[..near_idx].join(" ");
+    let right = words[near_idx + 1..].join(" ");
+    if left.is_empty() || right.is_empty() {
+        return None;
+    }
+
+    Some(PositionalQuery::Proximity(left, right, k))
+}
+
+/// Parses a query string into a boolean query tree via recursive descent, in
+/// ascending precedence: `OR` binds loosest, then implicit/explicit `AND`,
+/// then unary `NOT`/`-`, then parenthesized groups and leaves (bare words and
+/// quoted phrases). Quoted text (`"hello world"`) becomes a `Phrase`; bare
+/// words with no connective between them are implicitly ANDed together.
+pub fn parse_query(query: &str) -> Operation {
+    let mut parser = Parser { tokens: split_raw_tokens(query), pos: 0 };
+    parser.parse_or()
+}
+
+struct Parser {
+    tokens: Vec<RawToken>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&RawToken> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Consumes the next token if it's a bare word matching `word`
+    /// case-insensitively (used for the `OR`/`AND`/`NOT` keywords).
+    fn consume_keyword(&mut self, word: &str) -> bool {
+        match self.peek() {
+            Some(RawToken::Word(w)) if w.eq_ignore_ascii_case(word) => {
+                self.pos += 1;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether the next token can begin an AND-group operand, i.e. it isn't
+    /// `OR`, a closing paren, or end of input.
+    fn starts_operand(&self) -> bool {
+        match self.peek() {
+            None => false,
+            Some(RawToken::RParen) => false,
+            Some(RawToken::Word(w)) => !w.eq_ignore_ascii_case("or"),
+            _ => true,
+        }
+    }
+
+    fn parse_or(&mut self) -> Operation {
+        let mut operations = vec![self.parse_and()];
+        while self.consume_keyword("or") {
+            operations.push(self.parse_and());
+        }
+
+        if operations.len() == 1 {
+            operations.remove(0)
+        } else {
+            Operation::Or(operations)
+        }
+    }
+
+    fn parse_and(&mut self) -> Operation {
+        let mut operations = Vec::new();
+        loop {
+            self.consume_keyword("and");
+            if !self.starts_operand() {
+                break;
+            }
+            operations.push(self.parse_unary());
+        }
+
+        if operations.len() == 1 {
+            operations.remove(0)
+        } else {
+            Operation::And(operations)
+        }
+    }
+
+    fn parse_unary(&mut self) -> Operation {
+        if self.consume_keyword("not") {
+            return Operation::Not(Box::new(self.parse_unary()));
+        }
+
+        if let Some(RawToken::Word(word)) = self.peek() {
+            if word.starts_with('-') && word.len() > 1 {
+                let term = word.trim_start_matches('-').to_string();
+                self.pos += 1;
+                return Operation::Not(Box::new(leaf_operation(RawToken::Word(term))));
+            }
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Operation {
+        match self.tokens.get(self.pos).cloned() {
+            Some(RawToken::LParen) => {
+                self.pos += 1;
+                let operation = self.parse_or();
+                if matches!(self.peek(), Some(RawToken::RParen)) {
+                    self.pos += 1;
+                }
+                operation
+            },
+            Some(token @ (RawToken::Word(_) | RawToken::Quoted(_))) => {
+                self.pos += 1;
+                leaf_operation(token)
+            },
+            _ => Operation::And(vec![]),
+        }
+    }
+}
+
+fn leaf_operation(token: RawToken) -> Operation {
+    match token {
+        RawToken::Word(word) => {
+            let term = PositionalInvertedIndex::get_tokens(&word).into_iter().next().unwrap_or_default();
+            Operation::Term(term)
+        },
+        RawToken::Quoted(phrase) => Operation::Phrase(PositionalInvertedIndex::get_tokens(&phrase)),
+        RawToken::LParen | RawToken::RParen => unreachable!("leaf_operation only ever receives Word/Quoted tokens"),
+    }
+}
+
+/// Splits a raw query string into words, quoted phrases, and parentheses,
+/// keeping the contents of a `"..."` span together as a single `Quoted` token.
+fn split_raw_tokens(query: &str) -> Vec<RawToken> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = query.chars();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            if !current.is_empty() {
+                tokens.push(RawToken::Word(std::mem::take(&mut current)));
+            }
+        } else if c == '"' {
+            if !current.is_empty() {
+                tokens.push(RawToken::Word(std::mem::take(&mut current)));
+            }
+            let mut phrase = String::new();
+            for c in chars.by_ref() {
+                if c == '"' {
+                    break;
+                }
+                phrase.push(c);
+            }
+            tokens.push(RawToken::Quoted(phrase));
+        } else if c == '(' || c == ')' {
+            if !current.is_empty() {
+                tokens.push(RawToken::Word(std::mem::take(&mut current)));
+            }
+            tokens.push(if c == '(' { RawToken::LParen } else { RawToken::RParen });
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(RawToken::Word(current));
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_single_term() {
+        assert_eq!(parse_query("hello"), Operation::Term("hello".to_string()));
+    }
+
+    #[test]
+    fn test_parse_implicit_and() {
+        assert_eq!(
+            parse_query("hello world"),
+            Operation::And(vec![
+                Operation::Term("hello".to_string()),
+                Operation::Term("world".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_quoted_phrase() {
+        assert_eq!(
+            parse_query("\"hello world\""),
+            Operation::Phrase(vec!["hello".to_string(), "world".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_or() {
+        assert_eq!(
+            parse_query("hello OR world"),
+            Operation::Or(vec![
+                Operation::Term("hello".to_string()),
+                Operation::Term("world".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_leading_dash_negates() {
+        assert_eq!(
+            parse_query("hello -world"),
+            Operation::And(vec![
+                Operation::Term("hello".to_string()),
+                Operation::Not(Box::new(Operation::Term("world".to_string()))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_not_keyword_negates() {
+        assert_eq!(
+            parse_query("hello NOT world"),
+            Operation::And(vec![
+                Operation::Term("hello".to_string()),
+                Operation::Not(Box::new(Operation::Term("world".to_string()))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_not_quoted_phrase() {
+        assert_eq!(
+            parse_query("NOT \"hello world\""),
+            Operation::Not(Box::new(Operation::Phrase(vec!["hello".to_string(), "world".to_string()])))
+        );
+    }
+
+    #[test]
+    fn test_parse_mixed_and_or() {
+        assert_eq!(
+            parse_query("hello world OR rust"),
+            Operation::Or(vec![
+                Operation::And(vec![
+                    Operation::Term("hello".to_string()),
+                    Operation::Term("world".to_string()),
+                ]),
+                Operation::Term("rust".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_explicit_and() {
+        assert_eq!(
+            parse_query("hello AND world"),
+            Operation::And(vec![
+                Operation::Term("hello".to_string()),
+                Operation::Term("world".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_parenthesized_group_binds_tighter_than_or() {
+        assert_eq!(
+            parse_query("(hello OR world) AND rust"),
+            Operation::And(vec![
+                Operation::Or(vec![
+                    Operation::Term("hello".to_string()),
+                    Operation::Term("world".to_string()),
+                ]),
+                Operation::Term("rust".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_not_parenthesized_group() {
+        assert_eq!(
+            parse_query("NOT (hello OR world)"),
+            Operation::Not(Box::new(Operation::Or(vec![
+                Operation::Term("hello".to_string()),
+                Operation::Term("world".to_string()),
+            ])))
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_parentheses() {
+        assert_eq!(
+            parse_query("rust AND (hello OR (world AND cargo))"),
+            Operation::And(vec![
+                Operation::Term("rust".to_string()),
+                Operation::Or(vec![
+                    Operation::Term("hello".to_string()),
+                    Operation::And(vec![
+                        Operation::Term("world".to_string()),
+                        Operation::Term("cargo".to_string()),
+                    ]),
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_positional_query_quoted_phrase() {
+        assert_eq!(
+            parse_positional_query("\"hello world\""),
+            Some(PositionalQuery::Phrase("hello world".to_string())),
+        );
+    }
+
+    #[test]
+    fn test_parse_positional_query_near_operator() {
+        assert_eq!(
+            parse_positional_query("hello NEAR/3 world"),
+            Some(PositionalQuery::Proximity("hello".to_string(), "world".to_string(), 3)),
+        );
+    }
+
+    #[test]
+    fn test_parse_positional_query_near_operator_lowercase() {
+        assert_eq!(
+            parse_positional_query("hello near/2 world"),
+            Some(PositionalQuery::Proximity("hello".to_string(), "world".to_string(), 2)),
+        );
+    }
+
+    #[test]
+    fn test_parse_positional_query_multi_word_operands() {
+        assert_eq!(
+            parse_positional_query("new york NEAR/5 city hall"),
+            Some(PositionalQuery::Proximity("new york".to_string(), "city hall".to_string(), 5)),
+        );
+    }
+
+    #[test]
+    fn test_parse_positional_query_plain_query_is_none() {
+        assert_eq!(parse_positional_query("hello world"), None);
+    }
+}
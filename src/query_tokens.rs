@@ -5,7 +5,8 @@ use std::collections::HashMap;
 pub enum QueryTokenDistribution {
     Fixed,
     Uniform,
-    FromDocument
+    FromDocument,
+    Boolean
 }
 
 pub fn generate_queries_from_fixed_dictionary(num_queries: usize, max_tokens: usize) -> Vec<String> {
@@ -53,6 +54,46 @@ pub fn generate_queries_from_distribution(num_queries: usize, max_tokens: usize,
     queries
 }
 
+/// Builds queries for `search_boolean` by chaining fixed-dictionary terms with
+/// random `AND`/`OR` connectives and occasional `NOT`/parenthesized groups, so
+/// `benchmark_index` can time realistic mixed boolean workloads rather than
+/// only flat phrase queries.
+pub fn generate_boolean_queries_from_dictionary(num_queries: usize, max_tokens: usize) -> Vec<String> {
+    let dictionary = [
+        "The", "quantity", "respectable", "she", "announced"
+    ];
+    let connectives = ["AND", "OR"];
+
+    let mut rng = thread_rng();
+    let mut queries = Vec::with_capacity(num_queries);
+
+    for _ in 0..num_queries {
+        let term_count = rng.gen_range(2..=max_tokens.max(2));
+        let mut parts = Vec::with_capacity(term_count);
+        for i in 0..term_count {
+            let term = dictionary.choose(&mut rng).unwrap();
+            if i > 0 && rng.gen_bool(0.3) {
+                parts.push(format!("NOT {}", term));
+            } else {
+                parts.push(term.to_string());
+            }
+        }
+
+        let mut query = parts[0].clone();
+        for part in &parts[1..] {
+            let connective = connectives.choose(&mut rng).unwrap();
+            query = format!("{} {} {}", query, connective, part);
+        }
+        if rng.gen_bool(0.3) {
+            query = format!("({})", query);
+        }
+
+        queries.push(query);
+    }
+
+    queries
+}
+
 pub fn pull_query_from_paragraph(paragraph: &str, num_queries: usize, max_tokens: usize) -> Vec<String> {
     if paragraph.is_empty() || num_queries == 0 {
         return vec![];
@@ -183,6 +224,20 @@ mod tests {
         assert!(common_count > rare_count);
     }
 
+    #[test]
+    fn test_boolean_queries_generate_correct_number_of_queries() {
+        let queries = generate_boolean_queries_from_dictionary(10, 4);
+        assert_eq!(queries.len(), 10);
+    }
+
+    #[test]
+    fn test_boolean_queries_contain_a_connective() {
+        let queries = generate_boolean_queries_from_dictionary(10, 4);
+        for query in queries {
+            assert!(query.contains("AND") || query.contains("OR"));
+        }
+    }
+
     #[test]
     fn test_query_from_paragraph_basic_functionality() {
         let paragraph = "This is a test paragraph with several words";
@@ -0,0 +1,287 @@
+use serde::{
+    Serialize,
+    Deserialize
+};
+use std::collections::BTreeMap;
+
+/// Number of 64-bit words needed to bitmap every value a container's 16 low bits
+/// can hold (65536 bits / 64 bits per word).
+const BITMAP_WORDS: usize = 1024;
+
+/// Once an array container holds more than this many values, storing them as a
+/// dense bitmap is more compact (and faster to probe) than a sorted list.
+const ARRAY_TO_BITMAP_THRESHOLD: usize = 4096;
+
+#[derive(Clone, Serialize, Deserialize)]
+enum Container {
+    /// A sorted array of the container's low 16 bits, used while sparse.
+    Array(Vec<u16>),
+    /// A dense bitmap over all 65536 possible low-16-bit values, used once dense.
+    Bitmap(Vec<u64>),
+}
+
+impl Container {
+    fn new() -> Self {
+        Container::Array(Vec::new())
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Container::Array(values) => values.len(),
+            Container::Bitmap(words) => words.iter().map(|w| w.count_ones() as usize).sum(),
+        }
+    }
+
+    fn contains(&self, value: u16) -> bool {
+        match self {
+            Container::Array(values) => values.binary_search(&value).is_ok(),
+            Container::Bitmap(words) => {
+                let (word, bit) = (value as usize / 64, value as usize % 64);
+                (words[word] >> bit) & 1 == 1
+            },
+        }
+    }
+
+    fn insert(&mut self, value: u16) {
+        match self {
+            Container::Array(values) => {
+                if let Err(idx) = values.binary_search(&value) {
+                    values.insert(idx, value);
+                    if values.len() > ARRAY_TO_BITMAP_THRESHOLD {
+                        *self = self.to_bitmap();
+                    }
+                }
+            },
+            Container::Bitmap(words) => {
+                let (word, bit) = (value as usize / 64, value as usize % 64);
+                words[word] |= 1 << bit;
+            },
+        }
+    }
+
+    fn to_bitmap(&self) -> Container {
+        let mut words = vec![0u64; BITMAP_WORDS];
+        if let Container::Array(values) = self {
+            for &value in values {
+                let (word, bit) = (value as usize / 64, value as usize % 64);
+                words[word] |= 1 << bit;
+            }
+        }
+        Container::Bitmap(words)
+    }
+
+    fn iter(&self) -> Vec<u16> {
+        match self {
+            Container::Array(values) => values.clone(),
+            Container::Bitmap(words) => {
+                let mut values = Vec::new();
+                for (word_idx, &word) in words.iter().enumerate() {
+                    let mut remaining = word;
+                    while remaining != 0 {
+                        let bit = remaining.trailing_zeros() as usize;
+                        values.push((word_idx * 64 + bit) as u16);
+                        remaining &= remaining - 1;
+                    }
+                }
+                values
+            },
+        }
+    }
+
+    fn approximate_size_in_bytes(&self) -> usize {
+        match self {
+            Container::Array(values) => values.len() * std::mem::size_of::<u16>(),
+            Container::Bitmap(words) => words.len() * std::mem::size_of::<u64>(),
+        }
+    }
+}
+
+/// A roaring bitmap over document IDs: IDs are chunked by their high 16 bits into
+/// containers, each of which stores its low 16 bits as either a sorted array
+/// (sparse) or a dense bitmap (dense), giving compact storage and fast set algebra
+/// over large doc-id sets.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RoaringBitmap {
+    containers: BTreeMap<u16, Container>,
+}
+
+impl RoaringBitmap {
+    pub fn new() -> Self {
+        RoaringBitmap { containers: BTreeMap::new() }
+    }
+
+    pub fn insert(&mut self, doc_id: usize) {
+        let (high, low) = Self::split(doc_id);
+        self.containers.entry(high).or_insert_with(Container::new).insert(low);
+    }
+
+    pub fn contains(&self, doc_id: usize) -> bool {
+        let (high, low) = Self::split(doc_id);
+        self.containers.get(&high).map_or(false, |container| container.contains(low))
+    }
+
+    pub fn len(&self) -> usize {
+        self.containers.values().map(Container::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.containers.values().all(|container| container.len() == 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.containers.iter().flat_map(|(&high, container)| {
+            container.iter().into_iter().map(move |low| ((high as usize) << 16) | low as usize)
+        })
+    }
+
+    pub fn to_sorted_vec(&self) -> Vec<usize> {
+        self.iter().collect()
+    }
+
+    pub fn union(&self, other: &RoaringBitmap) -> RoaringBitmap {
+        let mut result = self.clone();
+        for doc_id in other.iter() {
+            result.insert(doc_id);
+        }
+        result
+    }
+
+    pub fn intersection(&self, other: &RoaringBitmap) -> RoaringBitmap {
+        let mut result = RoaringBitmap::new();
+        for doc_id in self.iter() {
+            if other.contains(doc_id) {
+                result.insert(doc_id);
+            }
+        }
+        result
+    }
+
+    pub fn difference(&self, other: &RoaringBitmap) -> RoaringBitmap {
+        let mut result = RoaringBitmap::new();
+        for doc_id in self.iter() {
+            if !other.contains(doc_id) {
+                result.insert(doc_id);
+            }
+        }
+        result
+    }
+
+    pub fn approximate_size_in_bytes(&self) -> usize {
+        self.containers.values().map(Container::approximate_size_in_bytes).sum::<usize>()
+            + self.containers.len() * std::mem::size_of::<u16>()
+    }
+
+    fn split(doc_id: usize) -> (u16, u16) {
+        ((doc_id >> 16) as u16, (doc_id & 0xFFFF) as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_is_empty() {
+        let bitmap = RoaringBitmap::new();
+        assert!(bitmap.is_empty());
+        assert_eq!(bitmap.len(), 0);
+    }
+
+    #[test]
+    fn test_insert_and_contains() {
+        let mut bitmap = RoaringBitmap::new();
+        bitmap.insert(5);
+        bitmap.insert(70000);
+        assert!(bitmap.contains(5));
+        assert!(bitmap.contains(70000));
+        assert!(!bitmap.contains(6));
+    }
+
+    #[test]
+    fn test_insert_is_idempotent() {
+        let mut bitmap = RoaringBitmap::new();
+        bitmap.insert(5);
+        bitmap.insert(5);
+        assert_eq!(bitmap.len(), 1);
+    }
+
+    #[test]
+    fn test_len_across_multiple_containers() {
+        let mut bitmap = RoaringBitmap::new();
+        bitmap.insert(1);
+        bitmap.insert(70000);
+        bitmap.insert(140000);
+        assert_eq!(bitmap.len(), 3);
+    }
+
+    #[test]
+    fn test_to_sorted_vec() {
+        let mut bitmap = RoaringBitmap::new();
+        for doc_id in [5, 1, 70000, 3] {
+            bitmap.insert(doc_id);
+        }
+        assert_eq!(bitmap.to_sorted_vec(), vec![1, 3, 5, 70000]);
+    }
+
+    #[test]
+    fn test_array_to_bitmap_conversion_preserves_membership() {
+        let mut bitmap = RoaringBitmap::new();
+        for doc_id in 0..(ARRAY_TO_BITMAP_THRESHOLD + 10) {
+            bitmap.insert(doc_id);
+        }
+        assert_eq!(bitmap.len(), ARRAY_TO_BITMAP_THRESHOLD + 10);
+        assert!(bitmap.contains(0));
+        assert!(bitmap.contains(ARRAY_TO_BITMAP_THRESHOLD + 9));
+        assert!(!bitmap.contains(ARRAY_TO_BITMAP_THRESHOLD + 10));
+    }
+
+    #[test]
+    fn test_union() {
+        let mut a = RoaringBitmap::new();
+        a.insert(1);
+        a.insert(2);
+        let mut b = RoaringBitmap::new();
+        b.insert(2);
+        b.insert(3);
+        assert_eq!(a.union(&b).to_sorted_vec(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let mut a = RoaringBitmap::new();
+        a.insert(1);
+        a.insert(2);
+        let mut b = RoaringBitmap::new();
+        b.insert(2);
+        b.insert(3);
+        assert_eq!(a.intersection(&b).to_sorted_vec(), vec![2]);
+    }
+
+    #[test]
+    fn test_difference() {
+        let mut a = RoaringBitmap::new();
+        a.insert(1);
+        a.insert(2);
+        let mut b = RoaringBitmap::new();
+        b.insert(2);
+        assert_eq!(a.difference(&b).to_sorted_vec(), vec![1]);
+    }
+
+    #[test]
+    fn test_approximate_size_in_bytes_grows_with_inserts() {
+        let mut bitmap = RoaringBitmap::new();
+        let initial = bitmap.approximate_size_in_bytes();
+        bitmap.insert(1);
+        assert!(bitmap.approximate_size_in_bytes() > initial);
+    }
+
+    #[test]
+    fn test_dense_container_is_not_larger_per_entry_than_sparse_cap() {
+        let mut bitmap = RoaringBitmap::new();
+        for doc_id in 0..(ARRAY_TO_BITMAP_THRESHOLD + 10) {
+            bitmap.insert(doc_id);
+        }
+        // A single dense container is a fixed 8KB regardless of how full it is.
+        assert_eq!(bitmap.approximate_size_in_bytes(), BITMAP_WORDS * std::mem::size_of::<u64>() + std::mem::size_of::<u16>());
+    }
+}